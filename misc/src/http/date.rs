@@ -0,0 +1,188 @@
+//! Dates as used in headers such as `Expires`, `Last-Modified` and `If-Modified-Since`.
+
+const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// A point in time with second precision, written in the IMF-fixdate format: `Sun, 06 Nov 1994 08:49:37 GMT`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug)]
+pub struct HttpDate {
+    seconds: u64,
+}
+
+impl HttpDate {
+    /// The date the given number of seconds after 1970-01-01 00:00:00 UTC.
+    pub fn from_unix(seconds: u64) -> Self {
+        Self { seconds }
+    }
+
+    /// Seconds since 1970-01-01 00:00:00 UTC.
+    pub fn unix(&self) -> u64 {
+        self.seconds
+    }
+
+    /// Parses an IMF-fixdate. The obsolete RFC 850 and asctime formats aren't accepted.
+    pub fn parse(value: &str) -> Option<Self> {
+        let bytes = value.as_bytes();
+        if bytes.len() != 29
+            || &bytes[3..5] != b", "
+            || bytes[7] != b' '
+            || bytes[11] != b' '
+            || bytes[16] != b' '
+            || bytes[19] != b':'
+            || bytes[22] != b':'
+            || &bytes[25..] != b" GMT"
+        {
+            return None;
+        }
+
+        if !DAYS.iter().any(|day| day.as_bytes() == &bytes[..3]) {
+            return None;
+        }
+
+        let day = digits(&bytes[5..7])?;
+        let month = MONTHS.iter().position(|m| m.as_bytes() == &bytes[8..11])? as u64 + 1;
+        let year = digits(&bytes[12..16])?;
+        let hour = digits(&bytes[17..19])?;
+        let minute = digits(&bytes[20..22])?;
+        let second = digits(&bytes[23..25])?;
+
+        // Leap seconds are allowed by the grammar but folded into the next minute.
+        if year < 1970
+            || day == 0
+            || day > days_in_month(year, month)
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
+            return None;
+        }
+
+        let days = days_from_civil(year, month, day);
+        Some(Self::from_unix(
+            days * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second,
+        ))
+    }
+}
+
+impl core::fmt::Display for HttpDate {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let days = self.seconds / SECONDS_PER_DAY;
+        let time = self.seconds % SECONDS_PER_DAY;
+        let (year, month, day) = civil_from_days(days);
+
+        // 1970-01-01 was a Thursday.
+        let weekday = DAYS[((days + 4) % 7) as usize];
+        write!(
+            f,
+            "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+            weekday,
+            day,
+            MONTHS[(month - 1) as usize],
+            year,
+            time / 3600,
+            time / 60 % 60,
+            time % 60
+        )
+    }
+}
+
+fn digits(bytes: &[u8]) -> Option<u64> {
+    bytes.iter().try_fold(0, |value, b| {
+        if b.is_ascii_digit() {
+            Some(value * 10 + u64::from(b - b'0'))
+        } else {
+            None
+        }
+    })
+}
+
+// `is_multiple_of` needs a newer compiler than the crate otherwise does.
+#[allow(clippy::manual_is_multiple_of)]
+fn is_leap_year(year: u64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for the date. Years are counted from March so that the leap day comes last.
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    // 719468 days separate 0000-03-01 and 1970-01-01.
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The `(year, month, day)` for a number of days since 1970-01-01. The inverse of `days_from_civil`.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use std::string::ToString;
+
+    #[test]
+    fn to_string_writes_imf_fixdate() {
+        assert_eq!(
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            HttpDate::from_unix(784_111_777).to_string()
+        );
+        assert_eq!(
+            "Thu, 01 Jan 1970 00:00:00 GMT",
+            HttpDate::from_unix(0).to_string()
+        );
+        assert_eq!(
+            "Thu, 29 Feb 2024 23:59:59 GMT",
+            HttpDate::from_unix(1_709_251_199).to_string()
+        );
+    }
+
+    #[test]
+    fn parse_round_trips() {
+        for seconds in [0, 784_111_777, 951_782_400, 1_709_251_199, 4_102_444_800].iter() {
+            let date = HttpDate::from_unix(*seconds);
+            assert_eq!(Some(date), HttpDate::parse(&date.to_string()));
+        }
+    }
+
+    #[test]
+    fn parse_invalid_returns_none() {
+        assert_eq!(None, HttpDate::parse("Sunday, 06-Nov-94 08:49:37 GMT"));
+        assert_eq!(None, HttpDate::parse("Sun Nov  6 08:49:37 1994"));
+        assert_eq!(None, HttpDate::parse("Sun, 31 Feb 1994 08:49:37 GMT"));
+        assert_eq!(None, HttpDate::parse("Sun, 06 Nov 1994 24:49:37 GMT"));
+        assert_eq!(None, HttpDate::parse("Sun, 06 Nov 1994 08:49:37 UTC"));
+    }
+}
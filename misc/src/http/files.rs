@@ -0,0 +1,576 @@
+//! Serving files from a directory. Only available with the `std` feature.
+
+use crate::http::date::HttpDate;
+use crate::http::handler::Handler;
+use crate::http::request::{Method, Request, MAX_PATH_BYTES};
+use crate::http::response::{
+    BodyProducer, ContentType, Header, Response, Status, CHUNK_BUFFER_BYTES,
+};
+use core::convert::TryFrom;
+use std::boxed::Box;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Component, Path as FsPath, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The file served for a route naming a directory.
+const INDEX_FILE: &str = "index.html";
+/// Separates the parts of a response to a request for several ranges.
+const BYTERANGES_BOUNDARY: &str = "ymir-byteranges-6f1c9e2a";
+
+/// Answers `GET` and `HEAD` requests with the files under a directory.
+///
+/// Responses carry an `ETag` and `Last-Modified`, and a request whose `If-None-Match` or `If-Modified-Since`
+/// shows the client already has the file is answered with a 304.
+/// A `Range` header is answered with just those parts of the file. With an `If-Range` the whole file is sent instead,
+/// unless it gives a `Last-Modified` date that is a strong validator, as the `ETag` is only a weak one.
+/// Routes with a `..` segment are refused with a 403, missing files get a 404 and other methods a 405.
+/// Each response owns the file it reads its body from, which is closed when the response is dropped.
+pub struct StaticFiles {
+    root: PathBuf,
+    prefix: &'static str,
+}
+
+impl StaticFiles {
+    /// Serves the files under `root`, with the route of the request as the path within it.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self {
+            root: root.into(),
+            prefix: "",
+        }
+    }
+
+    /// Only serves routes under the prefix, such as `/assets`, taking the rest of the route as the path within the directory.
+    pub fn with_prefix(mut self, prefix: &'static str) -> Self {
+        self.prefix = prefix.trim_end_matches('/');
+        self
+    }
+
+    pub fn root(&self) -> &FsPath {
+        &self.root
+    }
+
+    fn respond<'r>(&self, request: &Request<'r>) -> Result<Response<'r>, Status> {
+        let path = request.path();
+        let mut buffer = [0; MAX_PATH_BYTES];
+        let route = path
+            .decode_route(&mut buffer)
+            .map_err(|_| Status::BadRequest)?;
+        let route = match route.strip_prefix(self.prefix) {
+            Some(route) if route.is_empty() || route.starts_with('/') => route,
+            _ => return Err(Status::NotFound),
+        };
+
+        let mut file_path = self.resolve(route)?;
+        let mut file = File::open(&file_path).map_err(io_status)?;
+        let mut metadata = file.metadata().map_err(io_status)?;
+        if metadata.is_dir() {
+            file_path.push(INDEX_FILE);
+            file = File::open(&file_path).map_err(io_status)?;
+            metadata = file.metadata().map_err(io_status)?;
+        }
+        if !metadata.is_file() {
+            return Err(Status::NotFound);
+        }
+
+        let len = usize::try_from(metadata.len()).map_err(|_| Status::InternalServerError)?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|since_epoch| HttpDate::from_unix(since_epoch.as_secs()));
+        let etag = ETag {
+            modified: modified.map_or(0, |modified| modified.unix()),
+            len: metadata.len(),
+        };
+
+        // If-None-Match takes precedence, as in RFC 9110 section 13.2.2.
+        let not_modified = match request.header("If-None-Match") {
+            Some(tags) => etag.matches(tags),
+            None => match (modified, request.header("If-Modified-Since")) {
+                (Some(modified), Some(since)) => {
+                    HttpDate::parse(since).is_some_and(|since| modified <= since)
+                }
+                _ => false,
+            },
+        };
+
+        let mut response = Response::new(if not_modified {
+            Status::NotModified
+        } else {
+            Status::Ok
+        });
+        response
+            .set_formatted_header("ETag", format_args!("{}", etag))
+            .map_err(|_| Status::InternalServerError)?;
+        if let Some(modified) = modified {
+            response
+                .set_formatted_header("Last-Modified", format_args!("{}", modified))
+                .map_err(|_| Status::InternalServerError)?;
+        }
+        if not_modified {
+            return Ok(response);
+        }
+
+        let content_type = file_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(ContentType::from_extension)
+            .unwrap_or(ContentType::OctetStream);
+        response
+            .set_header(Header {
+                key: "Content-Type",
+                value: content_type.to_header_value(),
+            })
+            .map_err(|_| Status::InternalServerError)?;
+
+        response
+            .set_header(Header {
+                key: "Accept-Ranges",
+                value: "bytes",
+            })
+            .map_err(|_| Status::InternalServerError)?;
+
+        let file = OpenFile {
+            file: RefCell::new(file),
+        };
+        response.with_owned_body(Box::new(file), len);
+
+        // Only GET is answered with ranges, as in RFC 9110 section 14.2.
+        if let Some(ranges) = request.ranges().filter(|_| request.method() == Method::Get) {
+            // Ranges of a file that changed since the client got its copy would be spliced into the wrong one,
+            // so If-Range needs a strong validator, as in RFC 9110 section 13.1.5. The ETag is weak, so a tag never matches.
+            let current = match request.header("If-Range").map(str::trim) {
+                Some(tag) if tag.starts_with('"') || tag.starts_with("W/") => false,
+                Some(date) => modified.is_some_and(is_strong) && HttpDate::parse(date) == modified,
+                None => true,
+            };
+            if current {
+                response.with_ranges(&ranges, BYTERANGES_BOUNDARY);
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Maps the decoded route to a path under the root, refusing anything that could step outside it.
+    fn resolve(&self, route: &str) -> Result<PathBuf, Status> {
+        let mut path = self.root.clone();
+
+        for segment in route.split('/').filter(|segment| !segment.is_empty()) {
+            // A segment must name a single entry, which rules out `..`, drive prefixes and other separators.
+            let mut components = FsPath::new(segment).components();
+            match (components.next(), components.next()) {
+                (Some(Component::Normal(name)), None) => path.push(name),
+                (Some(Component::CurDir), None) => {}
+                _ => return Err(Status::Forbidden),
+            }
+        }
+
+        Ok(path)
+    }
+}
+
+impl Handler for StaticFiles {
+    fn handle<'r>(&self, request: &Request<'r>) -> Response<'r> {
+        if request.method() != Method::Get && request.method() != Method::Head {
+            let mut response = Response::new(Status::MethodNotAllowed);
+            // The response is still a valid 405 without the header.
+            let _ = response.set_header(Header {
+                key: "Allow",
+                value: "GET, HEAD",
+            });
+            return response;
+        }
+
+        self.respond(request).unwrap_or_else(Response::new)
+    }
+}
+
+/// Whether a `Last-Modified` date is a strong validator, which it is once it is at least a second in the past,
+/// as in RFC 9110 section 8.8.2.2. A file changed again within the same second would keep the same date.
+fn is_strong(modified: HttpDate) -> bool {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .is_ok_and(|now| modified.unix() < now.as_secs())
+}
+
+fn io_status(error: io::Error) -> Status {
+    match error.kind() {
+        io::ErrorKind::NotFound => Status::NotFound,
+        io::ErrorKind::PermissionDenied => Status::Forbidden,
+        _ => Status::InternalServerError,
+    }
+}
+
+/// The file a response is reading its body from.
+struct OpenFile {
+    file: RefCell<File>,
+}
+
+impl BodyProducer for OpenFile {
+    fn chunk<'s>(&'s self, index: usize, buffer: &'s mut [u8]) -> Option<&'s [u8]> {
+        let mut file = self.file.borrow_mut();
+        // Seek on every fragment, as the write passes may start over from the first.
+        let offset = index.checked_mul(CHUNK_BUFFER_BYTES)?;
+        file.seek(SeekFrom::Start(offset as u64)).ok()?;
+
+        let buffer = &mut buffer[..CHUNK_BUFFER_BYTES];
+        let mut len = 0;
+        while len < buffer.len() {
+            match file.read(&mut buffer[len..]) {
+                Ok(0) => break,
+                Ok(read) => len += read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                // The body comes up short, which the client sees as a broken message.
+                Err(_) => break,
+            }
+        }
+
+        if len == 0 {
+            None
+        } else {
+            Some(&buffer[..len])
+        }
+    }
+
+    fn fragment_len(&self) -> Option<usize> {
+        Some(CHUNK_BUFFER_BYTES)
+    }
+}
+
+/// A weak validator made from the modification time in seconds and the size.
+/// A file rewritten at the same length within the same second keeps its tag, so it can't be a strong one.
+#[derive(PartialEq, Copy, Clone, Debug)]
+struct ETag {
+    modified: u64,
+    len: u64,
+}
+
+impl ETag {
+    /// Parses a tag written by `Display`. Weak tags compare equal, as `If-None-Match` uses weak comparison.
+    fn parse(tag: &str) -> Option<Self> {
+        let tag = tag.strip_prefix("W/").unwrap_or(tag);
+        let (modified, len) = tag.strip_prefix('"')?.strip_suffix('"')?.split_once('-')?;
+
+        Some(Self {
+            modified: u64::from_str_radix(modified, 16).ok()?,
+            len: u64::from_str_radix(len, 16).ok()?,
+        })
+    }
+
+    /// Whether an `If-None-Match` value lists this tag or is `*`.
+    fn matches(&self, if_none_match: &str) -> bool {
+        if_none_match.trim() == "*"
+            || if_none_match
+                .split(',')
+                .any(|tag| Self::parse(tag.trim()) == Some(*self))
+    }
+}
+
+impl core::fmt::Display for ETag {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "W/\"{:x}-{:x}\"", self.modified, self.len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::request::parse;
+    use crate::http::response::{self, ParsedResponse};
+    use std::fs;
+    use std::string::String;
+    use std::vec::Vec;
+
+    /// A directory of files for one test, removed when dropped.
+    struct Fixture {
+        root: PathBuf,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(std::format!(
+                "ymir-files-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(root.join("public/docs")).unwrap();
+            fs::write(root.join("public/app.wasm"), b"\0asm").unwrap();
+            fs::write(root.join("public/docs/index.html"), b"<h1>docs</h1>").unwrap();
+            fs::write(root.join("secret.txt"), b"secret").unwrap();
+            Self { root }
+        }
+
+        fn files(&self) -> StaticFiles {
+            StaticFiles::new(self.root.join("public"))
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    /// Answers the raw request and returns the written response.
+    fn get(files: &StaticFiles, raw: &[u8]) -> Vec<u8> {
+        let request = parse(raw).unwrap();
+        let mut response = files.handle(&request);
+        response.with_request_method(request.method());
+
+        let mut out = std::vec![0; response.request_size()];
+        let len = response.write_to_buffer(&mut out).unwrap();
+        out.truncate(len);
+        out
+    }
+
+    fn parsed(out: &[u8]) -> ParsedResponse<'_> {
+        response::parse(out).unwrap()
+    }
+
+    #[test]
+    fn serves_file_with_validators() {
+        let fixture = Fixture::new("serve");
+        let out = get(&fixture.files(), b"GET /app.wasm HTTP/1.1\r\n\r\n");
+        let response = parsed(&out);
+
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(b"\0asm", response.body());
+        assert_eq!(Some("application/wasm"), response.header("Content-Type"));
+        assert_eq!(Some("4"), response.header("Content-Length"));
+        let etag = response.header("ETag").unwrap();
+        assert!(etag.starts_with("W/\"") && etag.ends_with("-4\""));
+        assert!(HttpDate::parse(response.header("Last-Modified").unwrap()).is_some());
+    }
+
+    #[test]
+    fn serves_index_of_directory_under_prefix() {
+        let fixture = Fixture::new("index");
+        let files = fixture.files().with_prefix("/static/");
+
+        let out = get(&files, b"GET /static/docs/ HTTP/1.1\r\n\r\n");
+        let response = parsed(&out);
+        assert_eq!(b"<h1>docs</h1>", response.body());
+        assert_eq!(Some("text/html"), response.header("Content-Type"));
+
+        let out = get(&files, b"GET /staticky/app.wasm HTTP/1.1\r\n\r\n");
+        assert_eq!(Status::NotFound, parsed(&out).status());
+    }
+
+    #[test]
+    fn head_sends_headers_only() {
+        let fixture = Fixture::new("head");
+        let out = get(&fixture.files(), b"HEAD /app.wasm HTTP/1.1\r\n\r\n");
+
+        assert!(out.ends_with(b"\r\n\r\n"));
+        assert!(!out.ends_with(b"\0asm"));
+    }
+
+    #[test]
+    fn matching_etag_gets_not_modified() {
+        let fixture = Fixture::new("etag");
+        let files = fixture.files();
+        let out = get(&files, b"GET /app.wasm HTTP/1.1\r\n\r\n");
+        let etag = String::from(parsed(&out).header("ETag").unwrap());
+
+        let raw = std::format!(
+            "GET /app.wasm HTTP/1.1\r\nIf-None-Match: \"0-0\", {}\r\n\r\n",
+            etag.strip_prefix("W/").unwrap()
+        );
+        let out = get(&files, raw.as_bytes());
+        let response = parsed(&out);
+        assert_eq!(Status::NotModified, response.status());
+        assert_eq!(Some(etag.as_str()), response.header("ETag"));
+        assert_eq!(None, response.header("Content-Length"));
+        assert!(out.ends_with(b"\r\n\r\n"));
+
+        let out = get(
+            &files,
+            b"GET /app.wasm HTTP/1.1\r\nIf-None-Match: \"0-0\"\r\n\r\n",
+        );
+        assert_eq!(Status::Ok, parsed(&out).status());
+    }
+
+    #[test]
+    fn unchanged_since_date_gets_not_modified() {
+        let fixture = Fixture::new("since");
+        let files = fixture.files();
+        let out = get(&files, b"GET /app.wasm HTTP/1.1\r\n\r\n");
+        let modified = String::from(parsed(&out).header("Last-Modified").unwrap());
+
+        let raw = std::format!(
+            "GET /app.wasm HTTP/1.1\r\nIf-Modified-Since: {}\r\n\r\n",
+            modified
+        );
+        assert_eq!(
+            Status::NotModified,
+            parsed(&get(&files, raw.as_bytes())).status()
+        );
+
+        let out = get(
+            &files,
+            b"GET /app.wasm HTTP/1.1\r\nIf-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n",
+        );
+        assert_eq!(Status::Ok, parsed(&out).status());
+    }
+
+    #[test]
+    fn refuses_traversal() {
+        let fixture = Fixture::new("traversal");
+        let files = fixture.files();
+
+        let out = get(&files, b"GET /../secret.txt HTTP/1.1\r\n\r\n");
+        assert_eq!(Status::Forbidden, parsed(&out).status());
+
+        let out = get(
+            &files,
+            b"GET /docs/%2e%2E/%2E%2e/secret.txt HTTP/1.1\r\n\r\n",
+        );
+        assert_eq!(Status::Forbidden, parsed(&out).status());
+    }
+
+    #[test]
+    fn missing_files_and_other_methods() {
+        let fixture = Fixture::new("missing");
+        let files = fixture.files();
+
+        let out = get(&files, b"GET /nope.js HTTP/1.1\r\n\r\n");
+        assert_eq!(Status::NotFound, parsed(&out).status());
+
+        let out = get(&files, b"DELETE /app.wasm HTTP/1.1\r\n\r\n");
+        let response = parsed(&out);
+        assert_eq!(Status::MethodNotAllowed, response.status());
+        assert_eq!(Some("GET, HEAD"), response.header("Allow"));
+    }
+
+    #[test]
+    fn body_reads_past_one_fragment() {
+        let fixture = Fixture::new("large");
+        let contents: Vec<u8> = (0..CHUNK_BUFFER_BYTES * 2 + 7)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        fs::write(fixture.root.join("public/large.bin"), &contents).unwrap();
+
+        let out = get(&fixture.files(), b"GET /large.bin HTTP/1.1\r\n\r\n");
+        assert!(out.ends_with(&contents));
+        assert_eq!(
+            Some("application/octet-stream"),
+            parsed(&out).header("Content-Type")
+        );
+    }
+
+    #[test]
+    fn responses_each_own_their_file() {
+        let fixture = Fixture::new("owned");
+        let files = fixture.files();
+        let wasm = parse(b"GET /app.wasm HTTP/1.1\r\n\r\n").unwrap();
+        let index = parse(b"GET /docs/ HTTP/1.1\r\n\r\n").unwrap();
+
+        // Both are alive at once, and the handler can be dropped before either is written.
+        let responses = [files.handle(&wasm), files.handle(&index)];
+        drop(files);
+
+        let mut buffer = [0; 512];
+        let len = responses[1].write_to_buffer(&mut buffer).unwrap();
+        assert_eq!(b"<h1>docs</h1>", parsed(&buffer[..len]).body());
+        let len = responses[0].write_to_buffer(&mut buffer).unwrap();
+        assert_eq!(b"\0asm", parsed(&buffer[..len]).body());
+    }
+
+    #[test]
+    fn serves_ranges_of_file() {
+        let fixture = Fixture::new("ranges");
+        let contents: Vec<u8> = (0..CHUNK_BUFFER_BYTES * 3)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        fs::write(fixture.root.join("public/large.bin"), &contents).unwrap();
+        let files = fixture.files();
+
+        let out = get(
+            &files,
+            b"GET /large.bin HTTP/1.1\r\nRange: bytes=1000-2100\r\n\r\n",
+        );
+        let response = parsed(&out);
+        assert_eq!(Status::PartialContent, response.status());
+        assert_eq!(
+            Some("bytes 1000-2100/3072"),
+            response.header("Content-Range")
+        );
+        assert_eq!(&contents[1000..=2100], response.body());
+
+        let out = get(
+            &files,
+            b"GET /large.bin HTTP/1.1\r\nRange: bytes=0-1, -2\r\n\r\n",
+        );
+        let response = parsed(&out);
+        assert_eq!(Status::PartialContent, response.status());
+        assert!(response
+            .header("Content-Type")
+            .unwrap()
+            .starts_with("multipart/byteranges; boundary="));
+        assert!(out.ends_with(std::format!("--{}--\r\n", BYTERANGES_BOUNDARY).as_bytes()));
+
+        let out = get(
+            &files,
+            b"GET /large.bin HTTP/1.1\r\nRange: bytes=5000-\r\n\r\n",
+        );
+        let response = parsed(&out);
+        assert_eq!(Status::RangeNotSatisfiable, response.status());
+        assert_eq!(Some("bytes */3072"), response.header("Content-Range"));
+
+        let out = get(
+            &files,
+            b"HEAD /large.bin HTTP/1.1\r\nRange: bytes=1000-2100\r\n\r\n",
+        );
+        let response = response::parse_response_to(Method::Head, &out, &mut []).unwrap();
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(Some("3072"), response.header("Content-Length"));
+        assert_eq!(None, response.header("Content-Range"));
+    }
+
+    #[test]
+    fn if_range_needs_strong_validator() {
+        let fixture = Fixture::new("if-range");
+        let path = fixture.root.join("public/app.wasm");
+        let old = UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let files = fixture.files();
+        let out = get(&files, b"GET /app.wasm HTTP/1.1\r\n\r\n");
+        let response = parsed(&out);
+        assert_eq!(Some("bytes"), response.header("Accept-Ranges"));
+        let etag = String::from(response.header("ETag").unwrap());
+        let modified = String::from(response.header("Last-Modified").unwrap());
+
+        let raw = std::format!(
+            "GET /app.wasm HTTP/1.1\r\nRange: bytes=1-\r\nIf-Range: {}\r\n\r\n",
+            modified
+        );
+        let out = get(&files, raw.as_bytes());
+        assert_eq!(Status::PartialContent, parsed(&out).status());
+        assert_eq!(b"asm", parsed(&out).body());
+
+        // The weak tag can't vouch for the ranges, nor can a date that doesn't match.
+        let raw = std::format!(
+            "GET /app.wasm HTTP/1.1\r\nRange: bytes=1-\r\nIf-Range: {}\r\n\r\n",
+            etag
+        );
+        let out = get(&files, raw.as_bytes());
+        assert_eq!(Status::Ok, parsed(&out).status());
+        assert_eq!(b"\0asm", parsed(&out).body());
+
+        let out = get(
+            &files,
+            b"GET /app.wasm HTTP/1.1\r\nRange: bytes=1-\r\nIf-Range: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n",
+        );
+        assert_eq!(Status::Ok, parsed(&out).status());
+    }
+}
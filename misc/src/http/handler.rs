@@ -0,0 +1,180 @@
+//! Composing request handlers out of middleware without an allocator.
+//!
+//! A `Handler` answers a request. Wrapping it with `Handler::with` puts a `Middleware` around it,
+//! which may answer first or adjust the response on its way out. Each layer is its own type, so the
+//! whole chain is known at compile time and lives wherever the handler does.
+
+use crate::http::request::Request;
+use crate::http::response::Response;
+
+/// Answers a request. Implemented for every `fn(&Request) -> Response` and matching closure.
+pub trait Handler {
+    fn handle<'r>(&self, request: &Request<'r>) -> Response<'r>;
+
+    /// Wraps the handler in the middleware. The last middleware added is the outermost,
+    /// so it sees the request first and the response last.
+    fn with<M: Middleware>(self, middleware: M) -> Chain<M, Self>
+    where
+        Self: Sized,
+    {
+        Chain::new(middleware, self)
+    }
+}
+
+impl<F> Handler for F
+where
+    F: for<'r> Fn(&Request<'r>) -> Response<'r>,
+{
+    fn handle<'r>(&self, request: &Request<'r>) -> Response<'r> {
+        self(request)
+    }
+}
+
+/// Runs around a handler. Both hooks do nothing unless overridden.
+pub trait Middleware {
+    /// Runs before the inner handler. Returning a response answers the request without calling it,
+    /// e.g. to reject a request that fails an auth check.
+    fn before<'r>(&self, _request: &Request<'r>) -> Option<Response<'r>> {
+        None
+    }
+
+    /// Runs on the outgoing response, including one returned early by `before`.
+    fn after<'r>(&self, _request: &Request<'r>, _response: &mut Response<'r>) {}
+}
+
+/// A handler wrapped in a middleware. Usually built with `Handler::with`.
+#[derive(Copy, Clone, Debug)]
+pub struct Chain<M, H> {
+    middleware: M,
+    inner: H,
+}
+
+impl<M, H> Chain<M, H> {
+    pub const fn new(middleware: M, inner: H) -> Self {
+        Self { middleware, inner }
+    }
+
+    pub fn middleware(&self) -> &M {
+        &self.middleware
+    }
+
+    pub fn inner(&self) -> &H {
+        &self.inner
+    }
+}
+
+impl<M: Middleware, H: Handler> Handler for Chain<M, H> {
+    fn handle<'r>(&self, request: &Request<'r>) -> Response<'r> {
+        let mut response = match self.middleware.before(request) {
+            Some(response) => response,
+            None => self.inner.handle(request),
+        };
+        self.middleware.after(request, &mut response);
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::request::parse;
+    use crate::http::response::{Header, Status};
+    use core::cell::Cell;
+
+    fn hello<'r>(_request: &Request<'r>) -> Response<'r> {
+        let mut response = Response::new(Status::Ok);
+        response.with_body(b"hello").unwrap();
+        response
+    }
+
+    /// Rejects requests without an `Authorization` header.
+    struct RequireAuth;
+
+    impl Middleware for RequireAuth {
+        fn before<'r>(&self, request: &Request<'r>) -> Option<Response<'r>> {
+            match request.header("Authorization") {
+                Some(_) => None,
+                None => Some(Response::new(Status::Unauthorized)),
+            }
+        }
+    }
+
+    /// Tags every response and counts the requests it sees.
+    struct Tag {
+        value: &'static str,
+        seen: Cell<usize>,
+    }
+
+    impl Tag {
+        fn new(value: &'static str) -> Self {
+            Self {
+                value,
+                seen: Cell::new(0),
+            }
+        }
+    }
+
+    impl Middleware for Tag {
+        fn before<'r>(&self, _request: &Request<'r>) -> Option<Response<'r>> {
+            self.seen.set(self.seen.get() + 1);
+            None
+        }
+
+        fn after<'r>(&self, _request: &Request<'r>, response: &mut Response<'r>) {
+            response
+                .append_header(Header {
+                    key: "X-Tag",
+                    value: self.value,
+                })
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn functions_are_handlers() {
+        let request = parse(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let response = hello.handle(&request);
+
+        assert_eq!(Status::Ok, response.status());
+    }
+
+    #[test]
+    fn middleware_passes_through_to_handler() {
+        let chain = hello.with(RequireAuth).with(Tag::new("outer"));
+        let request = parse(b"GET / HTTP/1.1\r\nAuthorization: yes\r\n\r\n").unwrap();
+        let response = chain.handle(&request);
+
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(Some("outer"), response.header("X-Tag"));
+        assert_eq!(1, chain.middleware().seen.get());
+    }
+
+    #[test]
+    fn middleware_short_circuits_inner_layers() {
+        let chain = hello.with(Tag::new("inner")).with(RequireAuth);
+        let request = parse(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let response = chain.handle(&request);
+
+        assert_eq!(Status::Unauthorized, response.status());
+        assert_eq!(None, response.header("X-Tag"));
+        assert_eq!(0, chain.inner().middleware().seen.get());
+    }
+
+    #[test]
+    fn after_hooks_run_innermost_first_on_early_responses() {
+        let chain = hello
+            .with(RequireAuth)
+            .with(Tag::new("first"))
+            .with(Tag::new("second"));
+        let request = parse(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let response = chain.handle(&request);
+
+        let tags = response
+            .headers()
+            .iter()
+            .filter(|(key, _)| *key == "X-Tag")
+            .map(|(_, value)| value);
+        assert!(tags.eq(["first", "second"].iter().copied()));
+        assert_eq!(Status::Unauthorized, response.status());
+    }
+}
@@ -1,2 +1,9 @@
+pub mod date;
+#[cfg(feature = "std")]
+pub mod files;
+pub mod handler;
 pub mod request;
 pub mod response;
+pub mod router;
+#[cfg(feature = "std")]
+pub mod server;
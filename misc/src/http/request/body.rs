@@ -0,0 +1,374 @@
+use super::error::Error;
+use super::header::Headers;
+use super::MAX_REQUEST_BYTES;
+use crate::parser::{find, RawIter};
+
+const NEWLINE: &[u8] = b"\r\n";
+
+/// How the length of a request body is communicated.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum BodyLength {
+    /// The request has no body.
+    None,
+    /// The body is exactly this many bytes, as given by `Content-Length`.
+    Fixed(usize),
+    /// The body is sent with `Transfer-Encoding: chunked` and must be decoded.
+    Chunked,
+}
+
+/// Determines how the body should be read from the given headers.
+/// A request with both `Content-Length` and `Transfer-Encoding`, or with several differing `Content-Length` values, is rejected.
+pub fn body_length(headers: &Headers) -> Result<BodyLength, Error> {
+    let content_length = content_length(headers, MAX_REQUEST_BYTES)?;
+
+    match (headers.get("Transfer-Encoding"), content_length) {
+        (Some(_), Some(_)) => Err(Error::ConflictingBodyLength),
+        // Chunked must be the final coding applied to a request body.
+        (Some(encoding), None) if ends_with_chunked(encoding) => Ok(BodyLength::Chunked),
+        (Some(_), None) => Err(Error::UnsupportedTransferEncoding),
+        (None, Some(len)) => Ok(BodyLength::Fixed(len)),
+        (None, None) => Ok(BodyLength::None),
+    }
+}
+
+/// The length given by the `Content-Length` headers, if there are any. Several must all agree.
+/// Shared with responses, which have no limit of their own and so pass `usize::MAX` as `max_bytes`.
+pub(crate) fn content_length(headers: &Headers, max_bytes: usize) -> Result<Option<usize>, Error> {
+    let mut content_length = None;
+    for header in headers
+        .iter()
+        .filter(|header| header.name().eq_ignore_ascii_case("Content-Length"))
+    {
+        let len = parse_content_length(header.value(), max_bytes)?;
+        match content_length {
+            Some(previous) if previous != len => return Err(Error::ConflictingBodyLength),
+            _ => content_length = Some(len),
+        }
+    }
+
+    Ok(content_length)
+}
+
+/// Whether `chunked` is the last coding in a `Transfer-Encoding` value.
+pub(crate) fn ends_with_chunked(encoding: &str) -> bool {
+    let last = encoding.rsplit(',').next().unwrap_or("").trim();
+    last.eq_ignore_ascii_case("chunked")
+}
+
+/// Reads the body from the iterator. Fixed length bodies borrow from the request; chunked bodies are decoded into `buffer`.
+pub fn parse_body<'a>(
+    request: &mut RawIter<'a>,
+    headers: &Headers,
+    buffer: &'a mut [u8],
+) -> Result<&'a [u8], Error> {
+    match body_length(headers)? {
+        BodyLength::None => Ok(&[]),
+        BodyLength::Fixed(len) => {
+            let data = request.data();
+            if data.len() < len {
+                return Err(Error::TruncatedBody);
+            }
+
+            request.advance(len);
+            Ok(&data[..len])
+        }
+        BodyLength::Chunked => decode_chunked(request, buffer, MAX_REQUEST_BYTES),
+    }
+}
+
+fn parse_content_length(value: &str, max_bytes: usize) -> Result<usize, Error> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Error::InvalidContentLength);
+    }
+
+    // Anything that does not fit in a usize is certainly over the limit.
+    let len = value.parse::<usize>().unwrap_or(usize::MAX);
+    if len > max_bytes {
+        return Err(Error::BodySizeExceeded {
+            max_bytes,
+            bytes: len,
+        });
+    }
+
+    Ok(len)
+}
+
+/// Decodes a chunked body of at most `max_bytes` into the buffer, returning the decoded bytes. Trailers are skipped.
+pub(crate) fn decode_chunked<'a>(
+    request: &mut RawIter,
+    buffer: &'a mut [u8],
+    max_bytes: usize,
+) -> Result<&'a [u8], Error> {
+    let mut len: usize = 0;
+
+    loop {
+        let size = parse_chunk_size(request, max_bytes)?;
+        if size == 0 {
+            break;
+        }
+
+        if len.saturating_add(size) > max_bytes {
+            return Err(Error::BodySizeExceeded {
+                max_bytes,
+                bytes: len.saturating_add(size),
+            });
+        }
+
+        let data = request.data();
+        if data.len() < size.saturating_add(NEWLINE.len()) {
+            return Err(Error::TruncatedBody);
+        }
+
+        if !data[size..].starts_with(NEWLINE) {
+            return Err(Error::MalformedChunk);
+        }
+
+        if buffer.len() < len + size {
+            return Err(Error::BodyBufferTooSmall {
+                max_bytes: buffer.len(),
+                bytes: len + size,
+            });
+        }
+
+        buffer[len..len + size].copy_from_slice(&data[..size]);
+        len += size;
+        request.advance(size + NEWLINE.len());
+    }
+
+    skip_trailers(request)?;
+
+    let buffer: &'a [u8] = buffer;
+    Ok(&buffer[..len])
+}
+
+/// Parses a `chunk-size [; chunk-ext] CRLF` line, advancing past it. A size too large for a usize is over any `max_bytes`.
+pub(super) fn parse_chunk_size(request: &mut RawIter, max_bytes: usize) -> Result<usize, Error> {
+    let data = request.data();
+    let end = match find(data, NEWLINE) {
+        Some(end) => end,
+        None => return Err(Error::TruncatedBody),
+    };
+
+    let line = &data[..end];
+    let digits = match line.iter().position(|b| *b == b';') {
+        Some(extension) => &line[..extension],
+        None => line,
+    };
+
+    if digits.is_empty() {
+        return Err(Error::MalformedChunk);
+    }
+
+    let mut size: usize = 0;
+    for digit in digits {
+        let value = match (*digit as char).to_digit(16) {
+            Some(value) => value as usize,
+            None => return Err(Error::MalformedChunk),
+        };
+
+        size = match size.checked_mul(16).and_then(|s| s.checked_add(value)) {
+            Some(size) => size,
+            None => {
+                return Err(Error::BodySizeExceeded {
+                    max_bytes,
+                    bytes: usize::MAX,
+                })
+            }
+        };
+    }
+
+    request.advance(end + NEWLINE.len());
+    Ok(size)
+}
+
+/// Skips any trailer fields up to and including the empty line ending the chunked body.
+fn skip_trailers(request: &mut RawIter) -> Result<(), Error> {
+    loop {
+        let data = request.data();
+        let end = match find(data, NEWLINE) {
+            Some(end) => end,
+            None => return Err(Error::TruncatedBody),
+        };
+
+        request.advance(end + NEWLINE.len());
+        if end == 0 {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::request::parse_headers;
+
+    fn headers(raw: &[u8]) -> Headers<'_> {
+        parse_headers(&mut RawIter::new(raw)).unwrap()
+    }
+
+    #[test]
+    fn body_length_without_headers_returns_none() {
+        let headers = headers(b"Host: example.com\r\n\r\n");
+        assert_eq!(Ok(BodyLength::None), body_length(&headers));
+    }
+
+    #[test]
+    fn body_length_content_length_returns_fixed() {
+        let headers = headers(b"Content-Length: 12\r\n\r\n");
+        assert_eq!(Ok(BodyLength::Fixed(12)), body_length(&headers));
+    }
+
+    #[test]
+    fn body_length_repeated_equal_content_length_returns_fixed() {
+        let headers = headers(b"Content-Length: 12\r\nContent-Length: 12\r\n\r\n");
+        assert_eq!(Ok(BodyLength::Fixed(12)), body_length(&headers));
+    }
+
+    #[test]
+    fn body_length_differing_content_length_returns_err() {
+        let headers = headers(b"Content-Length: 12\r\nContent-Length: 13\r\n\r\n");
+        assert_eq!(Err(Error::ConflictingBodyLength), body_length(&headers));
+    }
+
+    #[test]
+    fn body_length_content_length_and_chunked_returns_err() {
+        let headers = headers(b"Content-Length: 12\r\nTransfer-Encoding: chunked\r\n\r\n");
+        assert_eq!(Err(Error::ConflictingBodyLength), body_length(&headers));
+    }
+
+    #[test]
+    fn body_length_chunked_returns_chunked() {
+        let headers = headers(b"Transfer-Encoding: gzip, Chunked\r\n\r\n");
+        assert_eq!(Ok(BodyLength::Chunked), body_length(&headers));
+    }
+
+    #[test]
+    fn body_length_chunked_not_last_returns_err() {
+        let headers = headers(b"Transfer-Encoding: chunked, gzip\r\n\r\n");
+        assert_eq!(
+            Err(Error::UnsupportedTransferEncoding),
+            body_length(&headers)
+        );
+    }
+
+    #[test]
+    fn body_length_invalid_content_length_returns_err() {
+        let headers = headers(b"Content-Length: -1\r\n\r\n");
+        assert_eq!(Err(Error::InvalidContentLength), body_length(&headers));
+    }
+
+    #[test]
+    fn body_length_oversize_content_length_returns_err() {
+        let headers = headers(b"Content-Length: 99999999999999999999999\r\n\r\n");
+        assert_eq!(
+            Err(Error::BodySizeExceeded {
+                max_bytes: MAX_REQUEST_BYTES,
+                bytes: usize::MAX
+            }),
+            body_length(&headers)
+        );
+    }
+
+    #[test]
+    fn parse_body_fixed_returns_slice() {
+        let headers = headers(b"Content-Length: 5\r\n\r\n");
+        let mut request = RawIter::new(b"hello world");
+        let body = parse_body(&mut request, &headers, &mut []);
+
+        assert_eq!(Ok(&b"hello"[..]), body);
+        assert_eq!(b" world", request.data());
+    }
+
+    #[test]
+    fn parse_body_fixed_truncated_returns_err() {
+        let headers = headers(b"Content-Length: 20\r\n\r\n");
+        let mut request = RawIter::new(b"hello world");
+        let body = parse_body(&mut request, &headers, &mut []);
+
+        assert_eq!(Err(Error::TruncatedBody), body);
+    }
+
+    #[test]
+    fn parse_body_chunked_decodes_into_buffer() {
+        let headers = headers(b"Transfer-Encoding: chunked\r\n\r\n");
+        let mut request = RawIter::new(b"5\r\nhello\r\n6;ext=1\r\n world\r\n0\r\n\r\nnext");
+        let mut buffer = [0; 32];
+        let body = parse_body(&mut request, &headers, &mut buffer);
+
+        assert_eq!(Ok(&b"hello world"[..]), body);
+        assert_eq!(b"next", request.data());
+    }
+
+    #[test]
+    fn parse_body_chunked_skips_trailers() {
+        let headers = headers(b"Transfer-Encoding: chunked\r\n\r\n");
+        let mut request = RawIter::new(b"A\r\n0123456789\r\n0\r\nX-Trailer: yes\r\n\r\n");
+        let mut buffer = [0; 32];
+        let body = parse_body(&mut request, &headers, &mut buffer);
+
+        assert_eq!(Ok(&b"0123456789"[..]), body);
+        assert!(request.data().is_empty());
+    }
+
+    #[test]
+    fn parse_body_chunked_truncated_returns_err() {
+        let headers = headers(b"Transfer-Encoding: chunked\r\n\r\n");
+        let mut request = RawIter::new(b"A\r\n0123");
+        let mut buffer = [0; 32];
+        let body = parse_body(&mut request, &headers, &mut buffer);
+
+        assert_eq!(Err(Error::TruncatedBody), body);
+    }
+
+    #[test]
+    fn parse_body_chunked_missing_last_chunk_returns_err() {
+        let headers = headers(b"Transfer-Encoding: chunked\r\n\r\n");
+        let mut request = RawIter::new(b"2\r\nhi\r\n");
+        let mut buffer = [0; 32];
+        let body = parse_body(&mut request, &headers, &mut buffer);
+
+        assert_eq!(Err(Error::TruncatedBody), body);
+    }
+
+    #[test]
+    fn parse_body_chunked_invalid_size_returns_err() {
+        let headers = headers(b"Transfer-Encoding: chunked\r\n\r\n");
+        let mut request = RawIter::new(b"zz\r\nhi\r\n0\r\n\r\n");
+        let mut buffer = [0; 32];
+        let body = parse_body(&mut request, &headers, &mut buffer);
+
+        assert_eq!(Err(Error::MalformedChunk), body);
+    }
+
+    #[test]
+    fn parse_body_chunked_buffer_too_small_returns_err() {
+        let headers = headers(b"Transfer-Encoding: chunked\r\n\r\n");
+        let mut request = RawIter::new(b"5\r\nhello\r\n0\r\n\r\n");
+        let mut buffer = [0; 4];
+        let body = parse_body(&mut request, &headers, &mut buffer);
+
+        assert_eq!(
+            Err(Error::BodyBufferTooSmall {
+                max_bytes: 4,
+                bytes: 5
+            }),
+            body
+        );
+    }
+
+    #[test]
+    fn parse_body_chunked_oversize_returns_err() {
+        let headers = headers(b"Transfer-Encoding: chunked\r\n\r\n");
+        let mut request = RawIter::new(b"FFFFF\r\nhello\r\n0\r\n\r\n");
+        let mut buffer = [0; 4];
+        let body = parse_body(&mut request, &headers, &mut buffer);
+
+        assert_eq!(
+            Err(Error::BodySizeExceeded {
+                max_bytes: MAX_REQUEST_BYTES,
+                bytes: 0xFFFFF
+            }),
+            body
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use super::{HttpVersion, Method, Path};
+use crate::http::response::{
+    Header, HeaderTable, PassOutput, ResponseError, ResponseWriter, SizeCounter, SliceWriter,
+    StreamError,
+};
+
+/// Errors from adding headers to a `RequestBuilder`.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum BuildError {
+    HeaderCountExceeded {
+        max_headers: usize,
+    },
+    /// The formatted header values didn't fit in the builder's buffer.
+    HeaderBufferExceeded {
+        max_bytes: usize,
+    },
+}
+
+impl From<ResponseError> for BuildError {
+    fn from(error: ResponseError) -> Self {
+        match error {
+            ResponseError::HeaderLengthExceeded { max_headers } => {
+                BuildError::HeaderCountExceeded { max_headers }
+            }
+            ResponseError::HeaderBufferExceeded { max_bytes } => {
+                BuildError::HeaderBufferExceeded { max_bytes }
+            }
+        }
+    }
+}
+
+/// Builds an outgoing request and writes it in wire format.
+/// HTTP/1.1 requires a `Host` header, which must be added by the caller.
+pub struct RequestBuilder<'a> {
+    method: Method,
+    path: Path,
+    version: HttpVersion,
+    headers: HeaderTable<'a>,
+    body: &'a [u8],
+}
+
+impl<'a> RequestBuilder<'a> {
+    pub fn new(method: Method, path: Path) -> Self {
+        Self {
+            method,
+            path,
+            version: HttpVersion::Http11,
+            headers: HeaderTable::new(),
+            body: &[],
+        }
+    }
+
+    /// Sets the version written on the request line, HTTP/1.1 by default.
+    pub fn with_version(&mut self, version: HttpVersion) {
+        self.version = version;
+    }
+
+    pub fn with_body(&mut self, body: &'a [u8]) {
+        self.body = body;
+    }
+
+    /// Adds the header after any existing ones, keeping earlier headers with the same key.
+    pub fn with_header(&mut self, header: Header<'a>) -> Result<(), BuildError> {
+        Ok(self.headers.append(header)?)
+    }
+
+    /// Replaces any headers with the same key, compared case-insensitively, keeping the position of the first.
+    pub fn set_header(&mut self, header: Header<'a>) -> Result<(), BuildError> {
+        Ok(self.headers.set(header)?)
+    }
+
+    /// Adds a header whose value is formatted into the builder's own buffer.
+    pub fn with_formatted_header(
+        &mut self,
+        key: &'a str,
+        value: core::fmt::Arguments,
+    ) -> Result<(), BuildError> {
+        Ok(self.headers.append_formatted(key, value)?)
+    }
+
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the value of the first header with the key.
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers.get(key)
+    }
+
+    pub fn headers(&self) -> &HeaderTable<'a> {
+        &self.headers
+    }
+
+    /// Returns the size of the buffer that should be used for writing the request.
+    pub fn request_size(&self) -> usize {
+        let mut counter = SizeCounter::new();
+
+        // Counting never fails.
+        let _ = self.write_to(&mut counter);
+
+        counter.len()
+    }
+
+    /// Writes the request and body to the buffer, returning the number of written bytes.
+    pub fn write_to_buffer(&self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        let size = self.request_size();
+        if buffer.len() < size {
+            return Err(StreamError::BufferOverflow {
+                max_bytes: buffer.len(),
+                actual_size: size,
+            });
+        }
+
+        self.write_to(&mut SliceWriter::new(buffer))
+    }
+
+    /// Streams the request into the writer, returning the number of written bytes. Backpressure is handled as in `Response::write_to`.
+    pub fn write_to<W: ResponseWriter>(&self, writer: W) -> Result<usize, StreamError> {
+        self.resume_write_to(writer, 0)
+    }
+
+    /// Like `write_to`, but skips the first `offset` bytes, which were already written by an earlier call.
+    pub fn resume_write_to<W: ResponseWriter>(
+        &self,
+        writer: W,
+        offset: usize,
+    ) -> Result<usize, StreamError> {
+        let mut output = PassOutput::new(writer, offset);
+
+        // Request line
+        output.write(self.method.to_str().as_bytes())?;
+        output.write(b" ")?;
+        output.write(self.path.bytes())?;
+        output.write(b" ")?;
+        output.write(self.version.to_str().as_bytes())?;
+        output.write(b"\r\n")?;
+
+        // Write content length, unless it was set explicitly
+        if self.sends_content_length() {
+            use numtoa::NumToA;
+            let mut len_buff: [u8; 20] = [0; 20];
+            let len = self.body.len().numtoa(10, &mut len_buff);
+            write_header(&mut output, b"Content-Length", len)?;
+        }
+
+        for (key, value) in self.headers.iter() {
+            write_header(&mut output, key.as_bytes(), value.as_bytes())?;
+        }
+
+        output.write(b"\r\n")?;
+        output.write(self.body)?;
+        output.finish()
+    }
+
+    /// A `Content-Length` is added for any body, and for an empty one where the method expects a body.
+    fn sends_content_length(&self) -> bool {
+        let expects_body = matches!(self.method, Method::Post | Method::Put | Method::Patch);
+        (expects_body || !self.body.is_empty()) && !self.headers.contains("Content-Length")
+    }
+}
+
+fn write_header<W: ResponseWriter>(
+    output: &mut PassOutput<W>,
+    key: &[u8],
+    value: &[u8],
+) -> Result<(), StreamError> {
+    output.write(key)?;
+    output.write(b": ")?;
+    output.write(value)?;
+    output.write(b"\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::request::parse;
+    use crate::http::response::HEADER_COUNT;
+
+    fn host(request: &mut RequestBuilder) {
+        request
+            .with_header(Header {
+                key: "Host",
+                value: "api.example.com",
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn write_get_request() {
+        let mut path = Path::new("/users").unwrap();
+        path.push_query_param("name", "Ada L").unwrap();
+        let mut request = RequestBuilder::new(Method::Get, path);
+        host(&mut request);
+
+        let mut buffer = [0; 256];
+        let len = request.write_to_buffer(&mut buffer).unwrap();
+        assert_eq!(
+            &b"GET /users?name=Ada%20L HTTP/1.1\r\nHost: api.example.com\r\n\r\n"[..],
+            &buffer[..len]
+        );
+        assert_eq!(len, request.request_size());
+    }
+
+    #[test]
+    fn write_post_round_trips_through_parse() {
+        let mut request = RequestBuilder::new(Method::Post, Path::new("/users").unwrap());
+        host(&mut request);
+        request
+            .with_header(Header {
+                key: "Content-Type",
+                value: "application/json",
+            })
+            .unwrap();
+        request.with_body(b"{\"name\":\"Ada\"}");
+
+        let mut buffer = [0; 256];
+        let len = request.write_to_buffer(&mut buffer).unwrap();
+        let parsed = parse(&buffer[..len]).unwrap();
+
+        assert_eq!(Method::Post, parsed.method());
+        assert_eq!("/users", parsed.path().to_str());
+        assert_eq!(Some("14"), parsed.header("Content-Length"));
+        assert_eq!(Some("application/json"), parsed.header("Content-Type"));
+        assert_eq!(b"{\"name\":\"Ada\"}", parsed.body());
+    }
+
+    #[test]
+    fn write_empty_post_sends_zero_content_length() {
+        let request = RequestBuilder::new(Method::Post, Path::new("/ping").unwrap());
+
+        let mut buffer = [0; 256];
+        let len = request.write_to_buffer(&mut buffer).unwrap();
+        assert!(buffer[..len].ends_with(b"Content-Length: 0\r\n\r\n"));
+    }
+
+    #[test]
+    fn write_http10_request() {
+        let mut request = RequestBuilder::new(Method::Delete, Path::new("/users/1").unwrap());
+        request.with_version(HttpVersion::Http10);
+
+        let mut buffer = [0; 256];
+        let len = request.write_to_buffer(&mut buffer).unwrap();
+        assert!(buffer[..len].starts_with(b"DELETE /users/1 HTTP/1.0\r\n"));
+    }
+
+    #[test]
+    fn write_to_small_buffer_returns_err() {
+        let request = RequestBuilder::new(Method::Get, Path::new("/").unwrap());
+        let mut buffer = [0; 4];
+
+        assert_eq!(
+            Err(StreamError::BufferOverflow {
+                max_bytes: 4,
+                actual_size: request.request_size()
+            }),
+            request.write_to_buffer(&mut buffer)
+        );
+    }
+
+    #[test]
+    fn too_many_headers_returns_err() {
+        let mut request = RequestBuilder::new(Method::Get, Path::new("/").unwrap());
+        for _ in 0..HEADER_COUNT {
+            host(&mut request);
+        }
+
+        assert_eq!(
+            Err(BuildError::HeaderCountExceeded {
+                max_headers: HEADER_COUNT
+            }),
+            request.with_header(Header {
+                key: "Accept",
+                value: "*/*",
+            })
+        );
+    }
+}
@@ -0,0 +1,114 @@
+/// A single `name=value` pair from a `Cookie` header. Surrounding double quotes are removed from the value.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Cookie<'a> {
+    name: &'a str,
+    value: &'a str,
+}
+
+impl<'a> Cookie<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn value(&self) -> &'a str {
+        self.value
+    }
+}
+
+/// Iterator over the `;`-separated cookies of a `Cookie` header. Pairs without a `=` or a name are skipped.
+#[derive(Clone, Debug)]
+pub struct Cookies<'a> {
+    remaining: &'a str,
+}
+
+impl<'a> Cookies<'a> {
+    pub fn new(header: &'a str) -> Self {
+        Self { remaining: header }
+    }
+
+    /// Returns the value of the first cookie with the name. Cookie names are case-sensitive.
+    pub fn get(self, name: &str) -> Option<&'a str> {
+        let mut cookies = self;
+        cookies
+            .find(|cookie| cookie.name == name)
+            .map(|cookie| cookie.value)
+    }
+}
+
+impl<'a> Iterator for Cookies<'a> {
+    type Item = Cookie<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.remaining.is_empty() {
+            let pair = match self.remaining.find(';') {
+                Some(index) => {
+                    let pair = &self.remaining[..index];
+                    self.remaining = &self.remaining[index + 1..];
+                    pair
+                }
+                None => {
+                    let pair = self.remaining;
+                    self.remaining = "";
+                    pair
+                }
+            };
+
+            let (name, value) = match pair.find('=') {
+                Some(index) => (pair[..index].trim(), pair[index + 1..].trim()),
+                None => continue,
+            };
+
+            if name.is_empty() {
+                continue;
+            }
+
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+
+            return Some(Cookie { name, value });
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cookies_are_split_and_trimmed() {
+        let mut cookies = Cookies::new("session=abc123; theme=dark;lang=en");
+        assert_eq!(
+            Some(Cookie {
+                name: "session",
+                value: "abc123"
+            }),
+            cookies.next()
+        );
+        assert_eq!(Some("dark"), cookies.next().map(|c| c.value()));
+        assert_eq!(Some("lang"), cookies.next().map(|c| c.name()));
+        assert_eq!(None, cookies.next());
+    }
+
+    #[test]
+    fn cookies_skip_malformed_pairs() {
+        let cookies = Cookies::new("; flag; =nameless; a=\"quoted value\"; b=");
+        let mut found = [("", ""); 2];
+        for (i, cookie) in cookies.enumerate() {
+            found[i] = (cookie.name(), cookie.value());
+        }
+
+        assert_eq!([("a", "quoted value"), ("b", "")], found);
+    }
+
+    #[test]
+    fn get_is_case_sensitive() {
+        let cookies = Cookies::new("ID=upper; id=lower");
+        assert_eq!(Some("lower"), cookies.clone().get("id"));
+        assert_eq!(Some("upper"), cookies.clone().get("ID"));
+        assert_eq!(None, cookies.get("Id"));
+    }
+}
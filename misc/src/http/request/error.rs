@@ -1,7 +1,69 @@
+use crate::http::response::Status;
+
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub enum Error {
     HttpMethodParseFailed,
     PathParseError,
-    RequestSizeExceeded { max_bytes: usize, bytes: usize },
-    PathSizeExceeded { max_bytes: usize, bytes: usize },
+    MissingRequestTarget,
+    ExpectedSpace,
+    ExpectedNewline,
+    InvalidHttpVersion,
+    UnsupportedHttpVersion,
+    RequestSizeExceeded {
+        max_bytes: usize,
+        bytes: usize,
+    },
+    PathSizeExceeded {
+        max_bytes: usize,
+        bytes: usize,
+    },
+    MalformedHeader,
+    HeaderCountExceeded {
+        max_headers: usize,
+    },
+    HeaderValueSizeExceeded {
+        max_bytes: usize,
+        bytes: usize,
+    },
+    InvalidContentLength,
+    ConflictingBodyLength,
+    UnsupportedTransferEncoding,
+    MalformedChunk,
+    TruncatedBody,
+    BodySizeExceeded {
+        max_bytes: usize,
+        bytes: usize,
+    },
+    BodyBufferTooSmall {
+        max_bytes: usize,
+        bytes: usize,
+    },
+    InvalidPercentEncoding,
+    DecodeBufferTooSmall {
+        max_bytes: usize,
+        bytes: usize,
+    },
+    /// The body was read as a form, but the `Content-Type` says it is something else.
+    UnexpectedContentType,
+    MissingBoundary,
+    MalformedMultipart,
+}
+
+impl Error {
+    /// The status a server should answer with when a request fails to parse with this error.
+    pub fn status(&self) -> Status {
+        match self {
+            Error::RequestSizeExceeded { .. } | Error::BodySizeExceeded { .. } => {
+                Status::ContentTooLarge
+            }
+            Error::PathSizeExceeded { .. } => Status::UriTooLong,
+            Error::HeaderCountExceeded { .. } | Error::HeaderValueSizeExceeded { .. } => {
+                Status::RequestHeaderFieldsTooLarge
+            }
+            Error::UnsupportedHttpVersion => Status::HttpVersionNotSupported,
+            Error::UnsupportedTransferEncoding => Status::NotImplemented,
+            Error::UnexpectedContentType => Status::UnsupportedMediaType,
+            _ => Status::BadRequest,
+        }
+    }
 }
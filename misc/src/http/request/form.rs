@@ -0,0 +1,130 @@
+use super::error::Error;
+use super::query::{percent_decode, QueryParams};
+
+/// A decoded `name=value` field from an `application/x-www-form-urlencoded` body.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct FormField<'b> {
+    name: &'b str,
+    value: &'b str,
+}
+
+impl<'b> FormField<'b> {
+    pub fn name(&self) -> &'b str {
+        self.name
+    }
+
+    pub fn value(&self) -> &'b str {
+        self.value
+    }
+}
+
+/// Iterator over the fields of a form-urlencoded body, decoding each into the next free part of the buffer.
+/// Decoding never grows the data, so a buffer as long as the body always suffices.
+pub struct FormFields<'a, 'b> {
+    params: QueryParams<'a>,
+    buffer: &'b mut [u8],
+}
+
+impl<'a, 'b> FormFields<'a, 'b> {
+    /// Reads the fields of the body, which must be ASCII as the encoding requires.
+    pub fn new(body: &'a [u8], buffer: &'b mut [u8]) -> Result<Self, Error> {
+        let body = core::str::from_utf8(body).map_err(|_| Error::InvalidPercentEncoding)?;
+        Ok(Self {
+            params: QueryParams::new(body),
+            buffer,
+        })
+    }
+
+    /// Decodes into the front of the remaining buffer, handing that part out for `'b`.
+    fn decode(&mut self, encoded: &str) -> Result<&'b str, Error> {
+        let buffer = core::mem::take(&mut self.buffer);
+        let len = match percent_decode(encoded, true, buffer) {
+            Ok(decoded) => decoded.len(),
+            Err(error) => {
+                // Put the buffer back so the fields after a bad one still decode.
+                self.buffer = buffer;
+                return Err(error);
+            }
+        };
+        let (decoded, rest) = buffer.split_at_mut(len);
+        self.buffer = rest;
+
+        // `percent_decode` has already checked the bytes are UTF-8.
+        Ok(core::str::from_utf8(decoded).unwrap())
+    }
+}
+
+impl<'a, 'b> Iterator for FormFields<'a, 'b> {
+    type Item = Result<FormField<'b>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let param = self.params.next()?;
+        let field = self.decode(param.key()).and_then(|name| {
+            let value = self.decode(param.value())?;
+            Ok(FormField { name, value })
+        });
+
+        Some(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn form_fields_continue_after_bad_field() {
+        let body = b"bad=%zz&name%zz=x&good=yes";
+        let mut buffer = [0; 16];
+        let mut fields = FormFields::new(body, &mut buffer).unwrap();
+
+        assert_eq!(Some(Err(Error::InvalidPercentEncoding)), fields.next());
+        assert_eq!(Some(Err(Error::InvalidPercentEncoding)), fields.next());
+        let good = fields.next().unwrap().unwrap();
+        assert_eq!(("good", "yes"), (good.name(), good.value()));
+        assert!(fields.next().is_none());
+    }
+
+    #[test]
+    fn form_fields_decode_in_order() {
+        let body = b"name=Ada+Lovelace&email=ada%40example.com&empty=";
+        let mut buffer = [0; 64];
+        let mut fields = FormFields::new(body, &mut buffer).unwrap();
+
+        let name = fields.next().unwrap().unwrap();
+        let email = fields.next().unwrap().unwrap();
+        let empty = fields.next().unwrap().unwrap();
+        assert!(fields.next().is_none());
+
+        // Earlier fields stay valid while later ones are decoded.
+        assert_eq!(("name", "Ada Lovelace"), (name.name(), name.value()));
+        assert_eq!(("email", "ada@example.com"), (email.name(), email.value()));
+        assert_eq!(("empty", ""), (empty.name(), empty.value()));
+    }
+
+    #[test]
+    fn form_fields_body_sized_buffer_suffices() {
+        let body = b"a%20b=c%26d&x=y";
+        let mut buffer = [0; 15];
+        let fields = FormFields::new(body, &mut buffer).unwrap();
+        assert_eq!(2, fields.filter(|field| field.is_ok()).count());
+    }
+
+    #[test]
+    fn form_fields_small_buffer_returns_err() {
+        let mut buffer = [0; 4];
+        let mut fields = FormFields::new(b"name=value", &mut buffer).unwrap();
+
+        assert!(matches!(
+            fields.next(),
+            Some(Err(Error::DecodeBufferTooSmall { .. }))
+        ));
+    }
+
+    #[test]
+    fn form_fields_invalid_escape_returns_err() {
+        let mut buffer = [0; 16];
+        let mut fields = FormFields::new(b"a=%zz", &mut buffer).unwrap();
+        assert_eq!(Some(Err(Error::InvalidPercentEncoding)), fields.next());
+    }
+}
@@ -0,0 +1,289 @@
+use super::error::Error;
+use crate::parser::{find, RawIter};
+
+pub const MAX_HEADERS: usize = 32;
+pub const MAX_HEADER_VALUE_BYTES: usize = 1024;
+
+const NEWLINE: &[u8] = b"\r\n";
+
+/// A single `name: value` pair from the request. Both halves borrow from the raw request.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Header<'a> {
+    name: &'a str,
+    value: &'a str,
+}
+
+impl<'a> Header<'a> {
+    pub(super) fn new(name: &'a str, value: &'a str) -> Self {
+        Self { name, value }
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn value(&self) -> &'a str {
+        self.value
+    }
+}
+
+/// Fixed capacity table of the headers sent with a request, kept in the order they were received.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Headers<'a> {
+    headers: [Header<'a>; MAX_HEADERS],
+    len: usize,
+}
+
+impl<'a> Headers<'a> {
+    pub fn new() -> Self {
+        Self {
+            headers: [Header {
+                name: "",
+                value: "",
+            }; MAX_HEADERS],
+            len: 0,
+        }
+    }
+
+    /// Returns the value of the first header matching the given name. Names are compared case-insensitively.
+    pub fn get(&self, name: &str) -> Option<&'a str> {
+        self.iter()
+            .find(|header| header.name.eq_ignore_ascii_case(name))
+            .map(|header| header.value)
+    }
+
+    /// Iterates over the headers in the order they were received.
+    pub fn iter(&self) -> core::slice::Iter<'_, Header<'a>> {
+        self.headers[..self.len].iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub(super) fn push(&mut self, header: Header<'a>) -> Result<(), Error> {
+        if self.len >= MAX_HEADERS {
+            return Err(Error::HeaderCountExceeded {
+                max_headers: MAX_HEADERS,
+            });
+        }
+
+        self.headers[self.len] = header;
+        self.len += 1;
+        Ok(())
+    }
+}
+
+impl<'a> Default for Headers<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses header lines until the empty line that ends the header block, or until the data runs out.
+/// The iterator is left pointing at the first byte after the header block.
+pub fn parse_headers<'a>(request: &mut RawIter<'a>) -> Result<Headers<'a>, Error> {
+    let mut headers = Headers::new();
+
+    while !request.data().is_empty() {
+        let data = request.data();
+        let (line, line_len) = match find(data, NEWLINE) {
+            Some(end) => (&data[..end], end + NEWLINE.len()),
+            None => (data, data.len()),
+        };
+
+        request.advance(line_len);
+
+        if line.is_empty() {
+            break;
+        }
+
+        headers.push(parse_header(line)?)?;
+    }
+
+    Ok(headers)
+}
+
+fn parse_header(line: &[u8]) -> Result<Header<'_>, Error> {
+    let separator = match line.iter().position(|b| *b == b':') {
+        Some(separator) => separator,
+        None => return Err(Error::MalformedHeader),
+    };
+
+    let name = &line[..separator];
+    if name.is_empty() || !name.iter().all(|b| is_token_byte(*b)) {
+        return Err(Error::MalformedHeader);
+    }
+
+    let value = trim_whitespace(&line[separator + 1..]);
+    if value.len() > MAX_HEADER_VALUE_BYTES {
+        return Err(Error::HeaderValueSizeExceeded {
+            max_bytes: MAX_HEADER_VALUE_BYTES,
+            bytes: value.len(),
+        });
+    }
+
+    // Names are already known to be ASCII; values must still be checked.
+    let name = core::str::from_utf8(name).map_err(|_| Error::MalformedHeader)?;
+    let value = core::str::from_utf8(value).map_err(|_| Error::MalformedHeader)?;
+
+    Ok(Header { name, value })
+}
+
+/// Characters allowed in a header name, as defined by the `tchar` rule in RFC 9110.
+pub(super) fn is_token_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+fn trim_whitespace(mut bytes: &[u8]) -> &[u8] {
+    while let [b' ' | b'\t', rest @ ..] = bytes {
+        bytes = rest;
+    }
+
+    while let [rest @ .., b' ' | b'\t'] = bytes {
+        bytes = rest;
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::RawIter;
+
+    #[test]
+    fn parse_headers_returns_headers_in_order() {
+        let request = b"Host: example.com\r\nAccept: */*\r\n\r\n";
+        let mut request = RawIter::new(request);
+        let headers = parse_headers(&mut request).unwrap();
+
+        assert_eq!(2, headers.len());
+
+        let mut iter = headers.iter();
+        let host = iter.next().unwrap();
+        assert_eq!("Host", host.name());
+        assert_eq!("example.com", host.value());
+
+        let accept = iter.next().unwrap();
+        assert_eq!("Accept", accept.name());
+        assert_eq!("*/*", accept.value());
+
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn parse_headers_stops_after_empty_line() {
+        let request = b"Host: example.com\r\n\r\nbody";
+        let mut request = RawIter::new(request);
+        let headers = parse_headers(&mut request).unwrap();
+
+        assert_eq!(1, headers.len());
+        assert_eq!(b"body", request.data());
+    }
+
+    #[test]
+    fn parse_headers_trims_value_whitespace() {
+        let request = b"Host: \t example.com \t\r\n\r\n";
+        let mut request = RawIter::new(request);
+        let headers = parse_headers(&mut request).unwrap();
+
+        assert_eq!(Some("example.com"), headers.get("Host"));
+    }
+
+    #[test]
+    fn parse_headers_allows_empty_value() {
+        let request = b"X-Empty:\r\n\r\n";
+        let mut request = RawIter::new(request);
+        let headers = parse_headers(&mut request).unwrap();
+
+        assert_eq!(Some(""), headers.get("X-Empty"));
+    }
+
+    #[test]
+    fn get_is_case_insensitive() {
+        let request = b"Content-Type: text/html\r\n\r\n";
+        let mut request = RawIter::new(request);
+        let headers = parse_headers(&mut request).unwrap();
+
+        assert_eq!(Some("text/html"), headers.get("content-type"));
+        assert_eq!(Some("text/html"), headers.get("CONTENT-TYPE"));
+        assert_eq!(None, headers.get("Content-Length"));
+    }
+
+    #[test]
+    fn get_returns_first_match() {
+        let request = b"Accept: text/html\r\naccept: */*\r\n\r\n";
+        let mut request = RawIter::new(request);
+        let headers = parse_headers(&mut request).unwrap();
+
+        assert_eq!(Some("text/html"), headers.get("Accept"));
+    }
+
+    #[test]
+    fn parse_headers_missing_colon_returns_err() {
+        let request = b"Host example.com\r\n\r\n";
+        let mut request = RawIter::new(request);
+        let headers = parse_headers(&mut request);
+
+        assert_eq!(Err(Error::MalformedHeader), headers);
+    }
+
+    #[test]
+    fn parse_headers_whitespace_before_colon_returns_err() {
+        let request = b"Host : example.com\r\n\r\n";
+        let mut request = RawIter::new(request);
+        let headers = parse_headers(&mut request);
+
+        assert_eq!(Err(Error::MalformedHeader), headers);
+    }
+
+    #[test]
+    fn parse_headers_empty_name_returns_err() {
+        let request = b": example.com\r\n\r\n";
+        let mut request = RawIter::new(request);
+        let headers = parse_headers(&mut request);
+
+        assert_eq!(Err(Error::MalformedHeader), headers);
+    }
+
+    #[test]
+    fn parse_headers_too_many_returns_err() {
+        let line = b"X-A: b\r\n";
+        let mut request = [0; (MAX_HEADERS + 1) * 8];
+        for chunk in request.chunks_mut(line.len()) {
+            chunk.copy_from_slice(line);
+        }
+
+        let mut request = RawIter::new(&request);
+        let headers = parse_headers(&mut request);
+
+        assert_eq!(
+            Err(Error::HeaderCountExceeded {
+                max_headers: MAX_HEADERS
+            }),
+            headers
+        );
+    }
+
+    #[test]
+    fn parse_headers_oversize_value_returns_err() {
+        let mut request = [b'a'; MAX_HEADER_VALUE_BYTES + 5];
+        request[..3].copy_from_slice(b"X: ");
+
+        let mut request = RawIter::new(&request);
+        let headers = parse_headers(&mut request);
+
+        assert_eq!(
+            Err(Error::HeaderValueSizeExceeded {
+                max_bytes: MAX_HEADER_VALUE_BYTES,
+                bytes: MAX_HEADER_VALUE_BYTES + 2
+            }),
+            headers
+        );
+    }
+}
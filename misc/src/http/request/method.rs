@@ -1,4 +1,5 @@
 use super::error::Error;
+use super::header::is_token_byte;
 use crate::parser::RawIter;
 
 /// HTTP defines a set of request methods to indicate the desired action to be performed for a given resource. Although they can also be nouns, these request methods are sometimes referred to as HTTP verbs. Each of them implements a different semantic, but some common features are shared by a group of them: e.g. a request method can be safe, idempotent, or cacheable.
@@ -34,14 +35,43 @@ pub enum Method {
     Patch,
 }
 
+impl Method {
+    /// Every method, in the order they are declared.
+    pub const ALL: [Method; 9] = [
+        Method::Get,
+        Method::Head,
+        Method::Post,
+        Method::Put,
+        Method::Delete,
+        Method::Connect,
+        Method::Options,
+        Method::Trace,
+        Method::Patch,
+    ];
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Head => "HEAD",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+            Method::Connect => "CONNECT",
+            Method::Options => "OPTIONS",
+            Method::Trace => "TRACE",
+            Method::Patch => "PATCH",
+        }
+    }
+}
+
 macro_rules! parse_request_method {
     ($request:expr, [($str1:expr, $enum1:expr), $(($str:expr, $enum:expr)),*]) => {
-        if $request.data().starts_with($str1) {
+        if is_method($request.data(), $str1) {
             $request.advance($str1.len());
             Ok($enum1)
         }
         $(
-            else if $request.data().starts_with($str) {
+            else if is_method($request.data(), $str) {
                 $request.advance($str.len());
                 Ok($enum)
             }
@@ -53,6 +83,14 @@ macro_rules! parse_request_method {
     };
 }
 
+/// Whether the data starts with the given method as a whole token, so that `GETX` is not read as `GET`.
+/// The token ends at any byte that can't be part of it; whether that byte is the expected space is checked by the caller.
+// `Option::is_none_or` needs a newer compiler than the crate otherwise does.
+#[allow(clippy::unnecessary_map_or)]
+fn is_method(data: &[u8], method: &[u8]) -> bool {
+    data.starts_with(method) && data.get(method.len()).map_or(true, |b| !is_token_byte(*b))
+}
+
 pub fn parse_method(request: &mut RawIter) -> Result<Method, Error> {
     parse_request_method!(
         request,
@@ -210,6 +248,35 @@ mod tests {
         assert_eq!(b"PATCH".len(), request.index());
     }
 
+    #[test]
+    fn parse_method_followed_by_separator_returns_ok() {
+        let mut request = RawIter::new(b"GET/foo HTTP/1.1");
+        assert_eq!(Ok(Method::Get), parse_method(&mut request));
+
+        // The missing space is left for the request line to report
+        assert_eq!(b"/foo HTTP/1.1", request.data());
+    }
+
+    #[test]
+    fn parse_methodprefix_of_longer_token_returns_err() {
+        let request = b"GETX /foo";
+        let mut request = RawIter::new(request);
+        let method = parse_method(&mut request);
+
+        assert_eq!(Err(Error::HttpMethodParseFailed), method);
+
+        // Make sure the iterator was not advanced
+        assert_eq!(0, request.index());
+    }
+
+    #[test]
+    fn to_str_round_trips() {
+        for method in Method::ALL.iter() {
+            let mut request = RawIter::new(method.to_str().as_bytes());
+            assert_eq!(Ok(*method), parse_method(&mut request));
+        }
+    }
+
     #[test]
     fn parse_methodunable_to_parse_returns_err() {
         let request = b"some stuff";
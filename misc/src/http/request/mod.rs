@@ -1,14 +1,47 @@
+mod body;
+mod builder;
+mod cookie;
 mod error;
+mod form;
+mod header;
 mod method;
+mod multipart;
+mod path;
+mod query;
+mod range;
+mod stream;
+mod version;
+pub use body::{body_length, parse_body, BodyLength};
+pub(crate) use body::{content_length, decode_chunked, ends_with_chunked};
+pub use builder::{BuildError, RequestBuilder};
+pub use cookie::{Cookie, Cookies};
 pub use error::Error;
+pub use form::{FormField, FormFields};
+pub use header::{parse_headers, Header, Headers, MAX_HEADERS, MAX_HEADER_VALUE_BYTES};
 pub use method::{parse_method, Method};
+pub use multipart::{Multipart, Part};
+pub use path::{Path, MAX_PATH_BYTES};
+pub use query::{percent_decode, QueryParam, QueryParams};
+pub use range::{ByteRange, Ranges, MAX_RANGES};
+pub use stream::{ParseStatus, RequestParser};
+pub use version::{parse_version, HttpVersion};
 
+use crate::http::response::{ContentType, MediaType};
 use crate::parser::RawIter;
+use path::parse_path;
 
 pub const MAX_REQUEST_BYTES: usize = 8192;
-pub const MAX_PATH_BYTES: usize = 256;
 
-pub fn parse(raw_request: &[u8]) -> Result<Request, Error> {
+/// Parses a request. Chunked bodies require a buffer to decode into; use `parse_with_body_buffer` for those.
+pub fn parse(raw_request: &[u8]) -> Result<Request<'_>, Error> {
+    parse_with_body_buffer(raw_request, &mut [])
+}
+
+/// Parses a request, decoding a chunked body into `body_buffer`. Bodies sized by `Content-Length` borrow from `raw_request` and leave the buffer untouched.
+pub fn parse_with_body_buffer<'a>(
+    raw_request: &'a [u8],
+    body_buffer: &'a mut [u8],
+) -> Result<Request<'a>, Error> {
     if raw_request.len() > MAX_REQUEST_BYTES {
         return Err(Error::RequestSizeExceeded {
             max_bytes: MAX_REQUEST_BYTES,
@@ -17,18 +50,47 @@ pub fn parse(raw_request: &[u8]) -> Result<Request, Error> {
     }
 
     let mut raw_iter = RawIter::new(raw_request);
-    let method = parse_method(&mut raw_iter)?;
-    let path = parse_path(&mut raw_iter)?;
-    Ok(Request { method, path })
+    let head = parse_head(&mut raw_iter)?;
+    let body = parse_body(&mut raw_iter, &head.headers, body_buffer)?;
+    Ok(Request {
+        method: head.method,
+        path: head.path,
+        version: head.version,
+        headers: head.headers,
+        body,
+    })
+}
+
+/// The request line and headers of a request.
+struct Head<'a> {
+    method: Method,
+    path: Path,
+    version: HttpVersion,
+    headers: Headers<'a>,
+}
+
+/// Parses the request line and headers, leaving the iterator at the start of the body.
+fn parse_head<'a>(raw_iter: &mut RawIter<'a>) -> Result<Head<'a>, Error> {
+    let (method, path, version) = parse_request_line(raw_iter)?;
+    let headers = parse_headers(raw_iter)?;
+    Ok(Head {
+        method,
+        path,
+        version,
+        headers,
+    })
 }
 
 #[derive(PartialEq, Clone, Debug)]
-pub struct Request {
+pub struct Request<'a> {
     method: Method,
     path: Path,
+    version: HttpVersion,
+    headers: Headers<'a>,
+    body: &'a [u8],
 }
 
-impl<'a> Request {
+impl<'a> Request<'a> {
     pub fn method(&self) -> Method {
         self.method
     }
@@ -36,77 +98,318 @@ impl<'a> Request {
     pub fn path(&self) -> Path {
         self.path
     }
-}
 
-const HTTP_VERSION: &'static [u8] = b"HTTP/1.1";
+    pub fn version(&self) -> HttpVersion {
+        self.version
+    }
 
-#[derive(PartialEq, Copy, Clone)]
-pub struct Path {
-    path: [u8; MAX_PATH_BYTES],
-    len: usize,
-}
+    /// Returns the value of the first header with the given name, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&'a str> {
+        self.headers.get(name)
+    }
+
+    pub fn headers(&self) -> &Headers<'a> {
+        &self.headers
+    }
+
+    /// Whether the client wants the connection kept open after the response.
+    /// `Connection: close` or `Connection: keep-alive` decide it; otherwise HTTP/1.1 connections persist and HTTP/1.0 ones don't.
+    pub fn keep_alive(&self) -> bool {
+        let connection = self.header("Connection").unwrap_or("");
+        let has_option = |option: &str| {
+            connection
+                .split(',')
+                .any(|value| value.trim().eq_ignore_ascii_case(option))
+        };
+
+        if has_option("close") {
+            false
+        } else if has_option("keep-alive") {
+            true
+        } else {
+            self.version == HttpVersion::Http11
+        }
+    }
+
+    /// The ranges of the body asked for in the `Range` header.
+    /// `None` if there is none or it should be ignored; see `Ranges::parse`.
+    pub fn ranges(&self) -> Option<Ranges> {
+        self.header("Range").and_then(Ranges::parse)
+    }
+
+    /// Iterates over the cookies sent in the `Cookie` header.
+    pub fn cookies(&self) -> Cookies<'a> {
+        Cookies::new(self.header("Cookie").unwrap_or(""))
+    }
+
+    /// Returns the value of the first cookie with the name.
+    pub fn cookie(&self, name: &str) -> Option<&'a str> {
+        self.cookies().get(name)
+    }
+
+    pub fn body(&self) -> &'a [u8] {
+        self.body
+    }
 
-impl Path {
-    pub fn bytes(&self) -> &[u8] {
-        &self.path[..self.len]
+    /// The `Content-Type` header along with its charset and boundary, if the type is one that is known.
+    pub fn media_type(&self) -> Option<MediaType<'a>> {
+        self.header("Content-Type").and_then(MediaType::parse)
+    }
+
+    /// Iterates over the fields of an `application/x-www-form-urlencoded` body, decoding them into the buffer.
+    pub fn form_fields<'b>(&self, buffer: &'b mut [u8]) -> Result<FormFields<'a, 'b>, Error> {
+        match self.media_type() {
+            Some(media_type) if media_type.content_type() == ContentType::FormUrlEncoded => {
+                FormFields::new(self.body, buffer)
+            }
+            _ => Err(Error::UnexpectedContentType),
+        }
     }
 
-    pub fn to_str(&self) -> &str {
-        core::str::from_utf8(&self.path[..self.len]).unwrap()
+    /// Iterates over the parts of a `multipart/form-data` body, using the boundary from the `Content-Type` header.
+    pub fn multipart(&self) -> Result<Multipart<'a>, Error> {
+        let media_type = self.media_type().ok_or(Error::UnexpectedContentType)?;
+        if media_type.content_type() != ContentType::Multipart {
+            return Err(Error::UnexpectedContentType);
+        }
+
+        let boundary = media_type.boundary().ok_or(Error::MissingBoundary)?;
+        Multipart::new(self.body, boundary)
     }
 }
 
-impl core::fmt::Debug for Path {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        let data = core::str::from_utf8(&self.path[..self.len]).unwrap();
-        f.debug_struct("Path").field("path", &data).finish()
+/// Parses `method SP request-target SP HTTP-version CRLF`, advancing past it.
+fn parse_request_line(request: &mut RawIter) -> Result<(Method, Path, HttpVersion), Error> {
+    let method = parse_method(request)?;
+    expect(request, b" ", Error::ExpectedSpace)?;
+    let path = parse_path(request)?;
+    expect(request, b" ", Error::ExpectedSpace)?;
+    let version = parse_version(request)?;
+    expect(request, b"\r\n", Error::ExpectedNewline)?;
+    Ok((method, path, version))
+}
+
+fn expect(request: &mut RawIter, expected: &[u8], error: Error) -> Result<(), Error> {
+    if !request.data().starts_with(expected) {
+        return Err(error);
     }
+
+    request.advance(expected.len());
+    Ok(())
 }
 
-fn parse_path(request: &mut RawIter) -> Result<Path, Error> {
-    let mut found_route = false;
-    let mut end_index = 0;
-    while end_index < request.data().len() {
-        if request.data()[end_index..].starts_with(HTTP_VERSION) {
-            found_route = true;
-            break;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_returns_headers() {
+        let request = b"GET /index.html HTTP/1.1\r\nHost: example.com\r\nAccept: */*\r\n\r\n";
+        let request = parse(request).unwrap();
+
+        assert_eq!(Method::Get, request.method());
+        assert_eq!("/index.html", request.path().to_str());
+        assert_eq!(2, request.headers().len());
+        assert_eq!(Some("example.com"), request.header("host"));
+        assert_eq!(Some("*/*"), request.header("Accept"));
+    }
+
+    #[test]
+    fn parse_without_headers_returns_empty_headers() {
+        let request = b"GET / HTTP/1.1\r\n\r\n";
+        let request = parse(request).unwrap();
 
-        end_index += 1;
+        assert_eq!("/", request.path().to_str());
+        assert!(request.headers().is_empty());
     }
 
-    if !found_route {
-        end_index = 0;
+    #[test]
+    fn parse_returns_content_length_body() {
+        let request = b"POST /users HTTP/1.1\r\nContent-Length: 13\r\n\r\n{\"id\": \"abc\"}";
+        let request = parse(request).unwrap();
+
+        assert_eq!(Method::Post, request.method());
+        assert_eq!(b"{\"id\": \"abc\"}", request.body());
     }
 
-    let mut len = 0;
-    let path = {
-        let mut path = [0; MAX_PATH_BYTES];
-        if end_index >= MAX_PATH_BYTES {
-            return Err(Error::PathSizeExceeded {
+    #[test]
+    fn parse_without_body_returns_empty_body() {
+        let request = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let request = parse(request).unwrap();
+
+        assert!(request.body().is_empty());
+    }
+
+    #[test]
+    fn parse_with_body_buffer_decodes_chunked_body() {
+        let request =
+            b"PUT /file HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nWiki\r\n5\r\npedia\r\n0\r\n\r\n";
+        let mut buffer = [0; 16];
+        let request = parse_with_body_buffer(request, &mut buffer).unwrap();
+
+        assert_eq!(b"Wikipedia", request.body());
+    }
+
+    #[test]
+    fn parse_chunked_body_without_buffer_returns_err() {
+        let request =
+            b"PUT /file HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nWiki\r\n0\r\n\r\n";
+        let request = parse(request);
+
+        assert_eq!(
+            Err(Error::BodyBufferTooSmall {
+                max_bytes: 0,
+                bytes: 4
+            }),
+            request
+        );
+    }
+
+    #[test]
+    fn parse_truncated_body_returns_err() {
+        let request = b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nabc";
+        let request = parse(request);
+
+        assert_eq!(Err(Error::TruncatedBody), request);
+    }
+
+    #[test]
+    fn parse_returns_version() {
+        let request = parse(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+        assert_eq!(HttpVersion::Http10, request.version());
+
+        let request = parse(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(HttpVersion::Http11, request.version());
+    }
+
+    #[test]
+    fn parse_keeps_full_request_target() {
+        let request = parse(b"GET /search?q=a%20b HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!("/search?q=a%20b", request.path().to_str());
+    }
+
+    #[test]
+    fn parse_unknown_method_returns_err() {
+        let request = parse(b"GETX /foo HTTP/1.1\r\n\r\n");
+        assert_eq!(Err(Error::HttpMethodParseFailed), request);
+    }
+
+    #[test]
+    fn parse_missing_space_after_method_returns_err() {
+        let request = parse(b"GET/foo HTTP/1.1\r\n\r\n");
+        assert_eq!(Err(Error::ExpectedSpace), request);
+    }
+
+    #[test]
+    fn parse_double_space_after_method_returns_err() {
+        let request = parse(b"GET  /foo HTTP/1.1\r\n\r\n");
+        assert_eq!(Err(Error::MissingRequestTarget), request);
+    }
+
+    #[test]
+    fn parse_missing_version_returns_err() {
+        let request = parse(b"GET /foo\r\n\r\n");
+        assert_eq!(Err(Error::ExpectedSpace), request);
+
+        let request = parse(b"GET /foo \r\n\r\n");
+        assert_eq!(Err(Error::InvalidHttpVersion), request);
+    }
+
+    #[test]
+    fn parse_unsupported_version_returns_err() {
+        let request = parse(b"GET /foo HTTP/2.0\r\n\r\n");
+        assert_eq!(Err(Error::UnsupportedHttpVersion), request);
+    }
+
+    #[test]
+    fn parse_trailing_bytes_after_version_returns_err() {
+        let request = parse(b"GET /foo HTTP/1.1 extra\r\n\r\n");
+        assert_eq!(Err(Error::ExpectedNewline), request);
+
+        let request = parse(b"GET /foo HTTP/1.1");
+        assert_eq!(Err(Error::ExpectedNewline), request);
+    }
+
+    #[test]
+    fn parse_oversize_path_returns_err() {
+        let line_end = b" HTTP/1.1\r\n\r\n";
+        let mut request = [b'a'; MAX_PATH_BYTES + 32];
+        request[..5].copy_from_slice(b"GET /");
+        let end = 5 + MAX_PATH_BYTES;
+        request[end..end + line_end.len()].copy_from_slice(line_end);
+
+        let request = parse(&request[..end + line_end.len()]);
+        assert_eq!(
+            Err(Error::PathSizeExceeded {
                 max_bytes: MAX_PATH_BYTES,
-                bytes: end_index + 1,
-            });
-        }
+                bytes: MAX_PATH_BYTES + 1
+            }),
+            request
+        );
+    }
 
-        {
-            let s = match core::str::from_utf8(&request.data()[..end_index]) {
-                Ok(s) => s,
-                Err(e) => {
-                    return Err(Error::PathParseError);
-                }
-            }
-            .trim();
+    #[test]
+    fn parse_invalid_path_byte_returns_err() {
+        let request = parse(b"GET /a\tb HTTP/1.1\r\n\r\n");
+        assert_eq!(Err(Error::PathParseError), request);
+    }
 
-            for byte in s.as_bytes() {
-                path[len] = *byte;
-                len += 1;
-            }
-        }
+    #[test]
+    fn parse_malformed_header_returns_err() {
+        let request = b"GET / HTTP/1.1\r\nHost\r\n\r\n";
+        let request = parse(request);
+
+        assert_eq!(Err(Error::MalformedHeader), request);
+    }
+
+    #[test]
+    fn parse_returns_cookies() {
+        let request = b"GET / HTTP/1.1\r\nCookie: session=abc; theme=dark\r\n\r\n";
+        let request = parse(request).unwrap();
+
+        assert_eq!(Some("abc"), request.cookie("session"));
+        assert_eq!(Some("dark"), request.cookie("theme"));
+        assert_eq!(None, request.cookie("missing"));
+        assert_eq!(2, request.cookies().count());
+    }
+
+    #[test]
+    fn parse_returns_form_fields() {
+        let request = b"POST /signup HTTP/1.1\r\nContent-Type: application/x-www-form-urlencoded\r\nContent-Length: 15\r\n\r\nname=Ada&age=36";
+        let request = parse(request).unwrap();
+        let mut buffer = [0; 15];
+        let mut fields = request.form_fields(&mut buffer).unwrap();
 
-        path
-    };
+        assert_eq!(Some("Ada"), fields.next().unwrap().ok().map(|f| f.value()));
+        assert_eq!(Some("age"), fields.next().unwrap().ok().map(|f| f.name()));
+        assert!(request.multipart().is_err());
+    }
 
-    request.advance(end_index);
-    Ok(Path { path, len })
+    #[test]
+    fn parse_returns_multipart_parts() {
+        let request = b"POST /upload HTTP/1.1\r\nContent-Type: multipart/form-data; boundary=\"b\"\r\nContent-Length: 55\r\n\r\n--b\r\nContent-Disposition: form-data; name=a\r\n\r\n1\r\n--b--";
+        let request = parse(request).unwrap();
+        let mut parts = request.multipart().unwrap();
+
+        assert_eq!(Some("a"), parts.next().unwrap().unwrap().name());
+        assert!(parts.next().is_none());
+        assert_eq!(
+            Some(Error::UnexpectedContentType),
+            request.form_fields(&mut []).err()
+        );
+    }
+
+    #[test]
+    fn keep_alive_follows_connection_header_and_version() {
+        let keep_alive = |raw: &[u8]| parse(raw).unwrap().keep_alive();
+
+        assert!(keep_alive(b"GET / HTTP/1.1\r\n\r\n"));
+        assert!(!keep_alive(
+            b"GET / HTTP/1.1\r\nConnection: Upgrade, Close\r\n\r\n"
+        ));
+        assert!(!keep_alive(b"GET / HTTP/1.0\r\n\r\n"));
+        assert!(keep_alive(
+            b"GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n"
+        ));
+    }
 }
@@ -0,0 +1,264 @@
+use super::error::Error;
+use super::header::{parse_headers, Headers};
+use crate::http::response::{ContentType, MediaType};
+use crate::parser::{find, RawIter};
+
+const NEWLINE: &[u8] = b"\r\n";
+const DASHES: &[u8] = b"--";
+/// Boundaries are limited to 70 characters by RFC 2046.
+const MAX_BOUNDARY_BYTES: usize = 70;
+
+/// A single part of a `multipart/form-data` body. Everything borrows from the body.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Part<'a> {
+    headers: Headers<'a>,
+    body: &'a [u8],
+}
+
+impl<'a> Part<'a> {
+    /// Returns the value of the first header with the given name, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&'a str> {
+        self.headers.get(name)
+    }
+
+    pub fn headers(&self) -> &Headers<'a> {
+        &self.headers
+    }
+
+    /// The form field name, from the `Content-Disposition` header.
+    pub fn name(&self) -> Option<&'a str> {
+        self.disposition_param("name")
+    }
+
+    /// The name of the uploaded file, from the `Content-Disposition` header. Only file fields have one.
+    pub fn filename(&self) -> Option<&'a str> {
+        self.disposition_param("filename")
+    }
+
+    /// The type from the part's `Content-Type` header, if it is one that is known.
+    pub fn content_type(&self) -> Option<ContentType> {
+        self.header("Content-Type").and_then(ContentType::parse)
+    }
+
+    pub fn media_type(&self) -> Option<MediaType<'a>> {
+        self.header("Content-Type").and_then(MediaType::parse)
+    }
+
+    pub fn body(&self) -> &'a [u8] {
+        self.body
+    }
+
+    fn disposition_param(&self, key: &str) -> Option<&'a str> {
+        parameter(self.header("Content-Disposition")?, key)
+    }
+}
+
+/// Iterator over the parts of a `multipart/form-data` body. The preamble and epilogue are ignored.
+/// After an error the iterator is finished.
+#[derive(Clone, Debug)]
+pub struct Multipart<'a> {
+    remaining: &'a [u8],
+    boundary: &'a str,
+    done: bool,
+}
+
+impl<'a> Multipart<'a> {
+    /// Reads the parts separated by the boundary, which is usually taken from `MediaType::boundary`.
+    pub fn new(body: &'a [u8], boundary: &'a str) -> Result<Self, Error> {
+        if boundary.is_empty() || boundary.len() > MAX_BOUNDARY_BYTES {
+            return Err(Error::MissingBoundary);
+        }
+
+        // The first delimiter may start the body, without the CRLF that belongs to later ones.
+        let first = [DASHES, boundary.as_bytes()];
+        let start = if body.starts_with(DASHES) && body[DASHES.len()..].starts_with(first[1]) {
+            DASHES.len() + boundary.len()
+        } else {
+            let index = find_delimiter(body, boundary).ok_or(Error::MalformedMultipart)?;
+            index + delimiter_len(boundary)
+        };
+
+        Ok(Self {
+            remaining: &body[start..],
+            boundary,
+            done: false,
+        })
+    }
+
+    fn next_part(&mut self) -> Result<Option<Part<'a>>, Error> {
+        // The final delimiter is followed by two dashes.
+        if self.remaining.starts_with(DASHES) {
+            return Ok(None);
+        }
+
+        let mut raw_iter = RawIter::new(self.remaining);
+        while let [b' ' | b'\t', ..] = raw_iter.data() {
+            raw_iter.advance(1);
+        }
+
+        if !raw_iter.data().starts_with(NEWLINE) {
+            return Err(Error::MalformedMultipart);
+        }
+        raw_iter.advance(NEWLINE.len());
+
+        let headers = parse_headers(&mut raw_iter)?;
+        let data = raw_iter.data();
+        let end = find_delimiter(data, self.boundary).ok_or(Error::MalformedMultipart)?;
+
+        self.remaining = &data[end + delimiter_len(self.boundary)..];
+        Ok(Some(Part {
+            headers,
+            body: &data[..end],
+        }))
+    }
+}
+
+impl<'a> Iterator for Multipart<'a> {
+    type Item = Result<Part<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let part = self.next_part();
+        if !matches!(part, Ok(Some(_))) {
+            self.done = true;
+        }
+
+        part.transpose()
+    }
+}
+
+/// The length of `CRLF--boundary`.
+fn delimiter_len(boundary: &str) -> usize {
+    NEWLINE.len() + DASHES.len() + boundary.len()
+}
+
+/// Returns the index of the next `CRLF--boundary`.
+fn find_delimiter(data: &[u8], boundary: &str) -> Option<usize> {
+    let mut from = 0;
+    loop {
+        let index = from + find(&data[from..], b"\r\n--")?;
+        if data[index + NEWLINE.len() + DASHES.len()..].starts_with(boundary.as_bytes()) {
+            return Some(index);
+        }
+
+        from = index + 1;
+    }
+}
+
+/// Returns the value of a `key=value` parameter of a header such as `form-data; name="a"; filename="b.txt"`.
+/// Semicolons inside quoted values are kept, and the quotes are removed.
+fn parameter<'a>(value: &'a str, key: &str) -> Option<&'a str> {
+    let mut rest = value;
+
+    while !rest.is_empty() {
+        let end = parameter_end(rest);
+        let param = &rest[..end];
+        rest = rest.get(end + 1..).unwrap_or("");
+
+        let (name, value) = match param.find('=') {
+            Some(index) => (param[..index].trim(), param[index + 1..].trim()),
+            None => continue,
+        };
+
+        if name.eq_ignore_ascii_case(key) {
+            return Some(
+                value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value),
+            );
+        }
+    }
+
+    None
+}
+
+/// The index of the first `;` outside quotes, or the length if there is none.
+fn parameter_end(value: &str) -> usize {
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (i, b) in value.bytes().enumerate() {
+        match b {
+            _ if escaped => escaped = false,
+            b'\\' if quoted => escaped = true,
+            b'"' => quoted = !quoted,
+            b';' if !quoted => return i,
+            _ => {}
+        }
+    }
+
+    value.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = b"preamble\r\n\
+--XyZ\r\n\
+Content-Disposition: form-data; name=\"title\"\r\n\
+\r\n\
+Hello\r\n\
+--XyZ\r\n\
+Content-Disposition: form-data; name=\"upload\"; filename=\"a;b.txt\"\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+line one\r\n--not the boundary\r\n\
+--XyZ--\r\n\
+epilogue";
+
+    #[test]
+    fn multipart_yields_parts() {
+        let mut parts = Multipart::new(BODY, "XyZ").unwrap();
+
+        let title = parts.next().unwrap().unwrap();
+        assert_eq!(Some("title"), title.name());
+        assert_eq!(None, title.filename());
+        assert_eq!(None, title.content_type());
+        assert_eq!(b"Hello", title.body());
+
+        let upload = parts.next().unwrap().unwrap();
+        assert_eq!(Some("upload"), upload.name());
+        assert_eq!(Some("a;b.txt"), upload.filename());
+        assert_eq!(Some(ContentType::PlainText), upload.content_type());
+        assert_eq!(&b"line one\r\n--not the boundary"[..], upload.body());
+
+        assert!(parts.next().is_none());
+    }
+
+    #[test]
+    fn multipart_boundary_at_start() {
+        let body = b"--b\r\nContent-Disposition: form-data; name=a\r\n\r\n1\r\n--b--";
+        let mut parts = Multipart::new(body, "b").unwrap();
+
+        let part = parts.next().unwrap().unwrap();
+        assert_eq!(Some("a"), part.name());
+        assert_eq!(b"1", part.body());
+        assert!(parts.next().is_none());
+    }
+
+    #[test]
+    fn multipart_missing_boundary_returns_err() {
+        assert!(matches!(
+            Multipart::new(b"no parts here", "b"),
+            Err(Error::MalformedMultipart)
+        ));
+        assert!(matches!(
+            Multipart::new(b"--\r\n", ""),
+            Err(Error::MissingBoundary)
+        ));
+    }
+
+    #[test]
+    fn multipart_unterminated_part_returns_err_once() {
+        let body = b"--b\r\nContent-Disposition: form-data; name=a\r\n\r\ntruncated";
+        let mut parts = Multipart::new(body, "b").unwrap();
+
+        assert_eq!(Some(Err(Error::MalformedMultipart)), parts.next());
+        assert!(parts.next().is_none());
+    }
+}
@@ -0,0 +1,244 @@
+use super::error::Error;
+use super::query::{percent_decode, QueryParams};
+use crate::parser::RawIter;
+
+pub const MAX_PATH_BYTES: usize = 256;
+
+/// The request-target, as sent by the client. Both the route and the query are still percent-encoded.
+#[derive(PartialEq, Copy, Clone)]
+pub struct Path {
+    path: [u8; MAX_PATH_BYTES],
+    len: usize,
+}
+
+impl Path {
+    /// Creates a path for an outgoing request, such as `/users?page=2`. The target must already be percent-encoded.
+    pub fn new(target: &str) -> Result<Self, Error> {
+        from_target(target.as_bytes())
+    }
+
+    /// Appends `key=value` to the query, percent-encoding both halves.
+    /// On error the path is left unchanged.
+    pub fn push_query_param(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        let mut path = *self;
+        let separator = if path.query().is_some() { b'&' } else { b'?' };
+
+        path.push(separator)?;
+        path.push_encoded(key)?;
+        path.push(b'=')?;
+        path.push_encoded(value)?;
+
+        *self = path;
+        Ok(())
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.path[..self.len]
+    }
+
+    pub fn to_str(&self) -> &str {
+        core::str::from_utf8(&self.path[..self.len]).unwrap()
+    }
+
+    /// The part of the path before the `?`, still percent-encoded.
+    pub fn route(&self) -> &str {
+        let path = self.to_str();
+        match path.find('?') {
+            Some(index) => &path[..index],
+            None => path,
+        }
+    }
+
+    /// The part of the path after the `?`, if there is one.
+    pub fn query(&self) -> Option<&str> {
+        let path = self.to_str();
+        path.find('?').map(|index| &path[index + 1..])
+    }
+
+    /// Iterates over the `key=value` pairs of the query string, in order.
+    pub fn query_params(&self) -> QueryParams<'_> {
+        QueryParams::new(self.query().unwrap_or(""))
+    }
+
+    /// Returns the still-encoded value of the first query parameter whose decoded key matches.
+    pub fn query_param(&self, key: &str) -> Option<&str> {
+        self.query_params()
+            .find(|param| param.key_matches(key))
+            .map(|param| param.value())
+    }
+
+    /// Percent-decodes the route into the buffer.
+    pub fn decode_route<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, Error> {
+        percent_decode(self.route(), false, buffer)
+    }
+
+    fn push(&mut self, byte: u8) -> Result<(), Error> {
+        if self.len >= MAX_PATH_BYTES {
+            return Err(Error::PathSizeExceeded {
+                max_bytes: MAX_PATH_BYTES,
+                bytes: self.len + 1,
+            });
+        }
+
+        self.path[self.len] = byte;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Appends the value with everything but unreserved characters percent-encoded.
+    fn push_encoded(&mut self, value: &str) -> Result<(), Error> {
+        const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+        for b in value.bytes() {
+            if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+                self.push(b)?;
+            } else {
+                self.push(b'%')?;
+                self.push(HEX[usize::from(b >> 4)])?;
+                self.push(HEX[usize::from(b & 0xf)])?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl core::fmt::Debug for Path {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let data = core::str::from_utf8(&self.path[..self.len]).unwrap();
+        f.debug_struct("Path").field("path", &data).finish()
+    }
+}
+
+/// Parses the request-target, advancing past it. The target runs until the next space or newline.
+pub fn parse_path(request: &mut RawIter) -> Result<Path, Error> {
+    let data = request.data();
+    let len = data
+        .iter()
+        .position(|b| *b == b' ' || *b == b'\r')
+        .unwrap_or(data.len());
+    let path = from_target(&data[..len])?;
+    request.advance(len);
+    Ok(path)
+}
+
+/// Validates a request-target and copies it into a `Path`.
+fn from_target(target: &[u8]) -> Result<Path, Error> {
+    let len = target.len();
+    if len == 0 {
+        return Err(Error::MissingRequestTarget);
+    }
+
+    if len > MAX_PATH_BYTES {
+        return Err(Error::PathSizeExceeded {
+            max_bytes: MAX_PATH_BYTES,
+            bytes: len,
+        });
+    }
+
+    // Only visible ASCII is allowed; anything else must be percent-encoded.
+    if !target.iter().all(|b| b.is_ascii_graphic()) {
+        return Err(Error::PathParseError);
+    }
+
+    let mut path = [0; MAX_PATH_BYTES];
+    path[..len].copy_from_slice(target);
+    Ok(Path { path, len })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(raw: &[u8]) -> Path {
+        parse_path(&mut RawIter::new(raw)).unwrap()
+    }
+
+    #[test]
+    fn route_without_query_returns_whole_path() {
+        let path = path(b"/users/42");
+        assert_eq!("/users/42", path.route());
+        assert_eq!(None, path.query());
+    }
+
+    #[test]
+    fn route_and_query_are_split() {
+        let path = path(b"/search?q=a%20b&page=2");
+        assert_eq!("/search", path.route());
+        assert_eq!(Some("q=a%20b&page=2"), path.query());
+    }
+
+    #[test]
+    fn empty_query_is_some() {
+        let path = path(b"/search?");
+        assert_eq!("/search", path.route());
+        assert_eq!(Some(""), path.query());
+        assert_eq!(None, path.query_params().next());
+    }
+
+    #[test]
+    fn query_param_returns_encoded_value() {
+        let path = path(b"/search?q=a%20b&page=2");
+        assert_eq!(Some("a%20b"), path.query_param("q"));
+        assert_eq!(Some("2"), path.query_param("page"));
+        assert_eq!(None, path.query_param("missing"));
+    }
+
+    #[test]
+    fn query_param_matches_encoded_key() {
+        let path = path(b"/search?first%20name=ann");
+        assert_eq!(Some("ann"), path.query_param("first name"));
+    }
+
+    #[test]
+    fn decode_route_decodes_escapes() {
+        let path = path(b"/files/a%20b+c?x=1");
+        let mut buffer = [0; 32];
+        assert_eq!(Ok("/files/a b+c"), path.decode_route(&mut buffer));
+    }
+
+    #[test]
+    fn decode_route_invalid_escape_returns_err() {
+        let path = path(b"/files/a%2");
+        let mut buffer = [0; 32];
+        assert_eq!(
+            Err(Error::InvalidPercentEncoding),
+            path.decode_route(&mut buffer)
+        );
+    }
+
+    #[test]
+    fn new_validates_target() {
+        assert_eq!(
+            "/users?page=2",
+            Path::new("/users?page=2").unwrap().to_str()
+        );
+        assert_eq!(Err(Error::MissingRequestTarget), Path::new(""));
+        assert_eq!(Err(Error::PathParseError), Path::new("/a b"));
+    }
+
+    #[test]
+    fn push_query_param_encodes_and_decodes_back() {
+        let mut path = Path::new("/search").unwrap();
+        path.push_query_param("q", "a b&c").unwrap();
+        path.push_query_param("page", "2").unwrap();
+        assert_eq!("/search?q=a%20b%26c&page=2", path.to_str());
+
+        let mut buffer = [0; 32];
+        let value = path.query_params().next().unwrap();
+        assert_eq!(Ok("a b&c"), value.decode_value(&mut buffer));
+    }
+
+    #[test]
+    fn push_query_param_overflow_leaves_path_unchanged() {
+        let mut path = Path::new("/search").unwrap();
+        let long = [b'a'; MAX_PATH_BYTES];
+        let long = core::str::from_utf8(&long).unwrap();
+
+        assert!(matches!(
+            path.push_query_param("q", long),
+            Err(Error::PathSizeExceeded { .. })
+        ));
+        assert_eq!("/search", path.to_str());
+    }
+}
@@ -0,0 +1,244 @@
+use super::error::Error;
+
+/// A single `key=value` pair from a query string. Both halves are still percent-encoded.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct QueryParam<'a> {
+    key: &'a str,
+    value: &'a str,
+}
+
+impl<'a> QueryParam<'a> {
+    pub fn key(&self) -> &'a str {
+        self.key
+    }
+
+    pub fn value(&self) -> &'a str {
+        self.value
+    }
+
+    /// Percent-decodes the key into the buffer, treating `+` as a space.
+    pub fn decode_key<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, Error> {
+        percent_decode(self.key, true, buffer)
+    }
+
+    /// Percent-decodes the value into the buffer, treating `+` as a space.
+    pub fn decode_value<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, Error> {
+        percent_decode(self.value, true, buffer)
+    }
+
+    /// Whether the decoded key equals `key`. Keys that fail to decode never match.
+    pub fn key_matches(&self, key: &str) -> bool {
+        let mut expected = key.bytes();
+        let mut decoded = Decoder::new(self.key, true);
+
+        loop {
+            match (decoded.next(), expected.next()) {
+                (None, None) => return true,
+                (Some(Ok(a)), Some(b)) if a == b => {}
+                _ => return false,
+            }
+        }
+    }
+}
+
+/// Iterator over the `&`-separated pairs of a query string. Empty pairs are skipped and a pair without `=` has an empty value.
+#[derive(Clone, Debug)]
+pub struct QueryParams<'a> {
+    remaining: &'a str,
+}
+
+impl<'a> QueryParams<'a> {
+    pub fn new(query: &'a str) -> Self {
+        Self { remaining: query }
+    }
+}
+
+impl<'a> Iterator for QueryParams<'a> {
+    type Item = QueryParam<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.remaining.is_empty() {
+            let pair = match self.remaining.find('&') {
+                Some(index) => {
+                    let pair = &self.remaining[..index];
+                    self.remaining = &self.remaining[index + 1..];
+                    pair
+                }
+                None => {
+                    let pair = self.remaining;
+                    self.remaining = "";
+                    pair
+                }
+            };
+
+            if pair.is_empty() {
+                continue;
+            }
+
+            return Some(match pair.find('=') {
+                Some(index) => QueryParam {
+                    key: &pair[..index],
+                    value: &pair[index + 1..],
+                },
+                None => QueryParam {
+                    key: pair,
+                    value: "",
+                },
+            });
+        }
+
+        None
+    }
+}
+
+/// Percent-decodes `encoded` into the buffer, returning the decoded string.
+/// When `plus_as_space` is set, `+` decodes to a space as it does in query strings and form bodies.
+/// Malformed escapes, or escapes that decode to invalid UTF-8, return `Error::InvalidPercentEncoding`.
+pub fn percent_decode<'b>(
+    encoded: &str,
+    plus_as_space: bool,
+    buffer: &'b mut [u8],
+) -> Result<&'b str, Error> {
+    let mut len = 0;
+    for byte in Decoder::new(encoded, plus_as_space) {
+        let byte = byte?;
+        if len >= buffer.len() {
+            return Err(Error::DecodeBufferTooSmall {
+                max_bytes: buffer.len(),
+                bytes: len + 1,
+            });
+        }
+
+        buffer[len] = byte;
+        len += 1;
+    }
+
+    let buffer: &'b [u8] = buffer;
+    core::str::from_utf8(&buffer[..len]).map_err(|_| Error::InvalidPercentEncoding)
+}
+
+/// Yields the decoded bytes of a percent-encoded string one at a time.
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    plus_as_space: bool,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(encoded: &'a str, plus_as_space: bool) -> Self {
+        Self {
+            bytes: encoded.as_bytes(),
+            plus_as_space,
+        }
+    }
+}
+
+impl<'a> Iterator for Decoder<'a> {
+    type Item = Result<u8, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (byte, len) = match self.bytes {
+            [] => return None,
+            [b'%', high, low, ..] => match (hex_value(*high), hex_value(*low)) {
+                (Some(high), Some(low)) => (high << 4 | low, 3),
+                _ => return Some(Err(Error::InvalidPercentEncoding)),
+            },
+            [b'%', ..] => return Some(Err(Error::InvalidPercentEncoding)),
+            [b'+', ..] if self.plus_as_space => (b' ', 1),
+            [byte, ..] => (*byte, 1),
+        };
+
+        self.bytes = &self.bytes[len..];
+        Some(Ok(byte))
+    }
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_params_iterates_in_order() {
+        let mut params = QueryParams::new("q=a%20b&page=2&flag");
+
+        let q = params.next().unwrap();
+        assert_eq!(("q", "a%20b"), (q.key(), q.value()));
+
+        let page = params.next().unwrap();
+        assert_eq!(("page", "2"), (page.key(), page.value()));
+
+        let flag = params.next().unwrap();
+        assert_eq!(("flag", ""), (flag.key(), flag.value()));
+
+        assert_eq!(None, params.next());
+    }
+
+    #[test]
+    fn query_params_skips_empty_pairs() {
+        let mut params = QueryParams::new("&&a=1&&");
+        assert_eq!("a", params.next().unwrap().key());
+        assert_eq!(None, params.next());
+    }
+
+    #[test]
+    fn query_params_keeps_extra_equals_in_value() {
+        let mut params = QueryParams::new("token=abc==");
+        assert_eq!("abc==", params.next().unwrap().value());
+    }
+
+    #[test]
+    fn decode_value_decodes_plus_and_escapes() {
+        let param = QueryParams::new("q=caf%C3%A9+au+lait").next().unwrap();
+        let mut buffer = [0; 32];
+        assert_eq!(Ok("café au lait"), param.decode_value(&mut buffer));
+    }
+
+    #[test]
+    fn percent_decode_keeps_plus_without_flag() {
+        let mut buffer = [0; 32];
+        assert_eq!(Ok("a+b c"), percent_decode("a+b%20c", false, &mut buffer));
+    }
+
+    #[test]
+    fn percent_decode_invalid_escape_returns_err() {
+        let mut buffer = [0; 32];
+        for encoded in ["%", "%2", "%zz", "a%g1"].iter() {
+            assert_eq!(
+                Err(Error::InvalidPercentEncoding),
+                percent_decode(encoded, true, &mut buffer)
+            );
+        }
+    }
+
+    #[test]
+    fn percent_decode_invalid_utf8_returns_err() {
+        let mut buffer = [0; 32];
+        assert_eq!(
+            Err(Error::InvalidPercentEncoding),
+            percent_decode("%FF", true, &mut buffer)
+        );
+    }
+
+    #[test]
+    fn percent_decode_buffer_too_small_returns_err() {
+        let mut buffer = [0; 2];
+        assert_eq!(
+            Err(Error::DecodeBufferTooSmall {
+                max_bytes: 2,
+                bytes: 3
+            }),
+            percent_decode("abc", true, &mut buffer)
+        );
+    }
+
+    #[test]
+    fn key_matches_compares_decoded_key() {
+        let param = QueryParams::new("a%20b+c=1").next().unwrap();
+        assert!(param.key_matches("a b c"));
+        assert!(!param.key_matches("a b"));
+        assert!(!param.key_matches("a%20b+c"));
+    }
+}
@@ -0,0 +1,147 @@
+/// The most ranges kept from a `Range` header. A header asking for more is ignored.
+pub const MAX_RANGES: usize = 8;
+
+/// One range of a `bytes` `Range` header, before it is resolved against the length of the body.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum ByteRange {
+    /// `first-last`, both inclusive.
+    FromTo(u64, u64),
+    /// `first-`, through the end of the body.
+    From(u64),
+    /// `-length`, the last `length` bytes of the body.
+    Suffix(u64),
+}
+
+impl ByteRange {
+    /// Returns the first and last byte, inclusive, this range covers in a body of `len` bytes,
+    /// or `None` if it covers none of it.
+    pub fn resolve(&self, len: u64) -> Option<(u64, u64)> {
+        match *self {
+            ByteRange::FromTo(first, last) if first < len => Some((first, last.min(len - 1))),
+            ByteRange::From(first) if first < len => Some((first, len - 1)),
+            ByteRange::Suffix(suffix) if suffix > 0 && len > 0 => {
+                Some((len - suffix.min(len), len - 1))
+            }
+            _ => None,
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let (first, last) = value.split_once('-')?;
+        let number = |digits: &str| {
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                None
+            } else {
+                digits.parse::<u64>().ok()
+            }
+        };
+
+        match (first.is_empty(), last.is_empty()) {
+            (true, false) => Some(ByteRange::Suffix(number(last)?)),
+            (false, true) => Some(ByteRange::From(number(first)?)),
+            (false, false) => {
+                let (first, last) = (number(first)?, number(last)?);
+                if first <= last {
+                    Some(ByteRange::FromTo(first, last))
+                } else {
+                    None
+                }
+            }
+            (true, true) => None,
+        }
+    }
+}
+
+/// The ranges of a `Range` header, in the order they were asked for.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Ranges {
+    ranges: [ByteRange; MAX_RANGES],
+    len: usize,
+}
+
+impl Ranges {
+    /// Parses a `Range` header value such as `bytes=0-499, -500`.
+    /// Returns `None` for other units, malformed values or more than `MAX_RANGES` ranges,
+    /// all of which should be answered as if there was no `Range` header.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (unit, list) = value.split_once('=')?;
+        if !unit.trim().eq_ignore_ascii_case("bytes") {
+            return None;
+        }
+
+        let mut ranges = Self {
+            ranges: [ByteRange::From(0); MAX_RANGES],
+            len: 0,
+        };
+        // Empty list elements are allowed and skipped, as in RFC 9110 section 5.6.1.
+        for range in list.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            let range = ByteRange::parse(range)?;
+            *ranges.ranges.get_mut(ranges.len)? = range;
+            ranges.len += 1;
+        }
+
+        if ranges.is_empty() {
+            None
+        } else {
+            Some(ranges)
+        }
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, ByteRange> {
+        self.ranges[..self.len].iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_and_multiple_ranges() {
+        let ranges = Ranges::parse("bytes=0-499").unwrap();
+        assert!(ranges.iter().eq([ByteRange::FromTo(0, 499)].iter()));
+
+        let ranges = Ranges::parse("Bytes= 500-, -200 ,, 3-3").unwrap();
+        assert!(ranges.iter().eq([
+            ByteRange::From(500),
+            ByteRange::Suffix(200),
+            ByteRange::FromTo(3, 3)
+        ]
+        .iter()));
+    }
+
+    #[test]
+    fn rejects_malformed_ranges() {
+        assert_eq!(None, Ranges::parse("items=0-1"));
+        assert_eq!(None, Ranges::parse("bytes=5-4"));
+        assert_eq!(None, Ranges::parse("bytes=-"));
+        assert_eq!(None, Ranges::parse("bytes=1-2, x"));
+        assert_eq!(None, Ranges::parse("bytes=+1-2"));
+        assert_eq!(None, Ranges::parse("bytes="));
+        assert_eq!(
+            None,
+            Ranges::parse("bytes=0-0,1-1,2-2,3-3,4-4,5-5,6-6,7-7,8-8")
+        );
+    }
+
+    #[test]
+    fn resolves_against_length() {
+        assert_eq!(Some((0, 9)), ByteRange::FromTo(0, 99).resolve(10));
+        assert_eq!(Some((4, 9)), ByteRange::From(4).resolve(10));
+        assert_eq!(Some((7, 9)), ByteRange::Suffix(3).resolve(10));
+        assert_eq!(Some((0, 9)), ByteRange::Suffix(30).resolve(10));
+
+        assert_eq!(None, ByteRange::FromTo(10, 20).resolve(10));
+        assert_eq!(None, ByteRange::From(10).resolve(10));
+        assert_eq!(None, ByteRange::Suffix(0).resolve(10));
+        assert_eq!(None, ByteRange::Suffix(5).resolve(0));
+    }
+}
@@ -0,0 +1,433 @@
+use super::body::{body_length, parse_chunk_size, BodyLength};
+use super::error::Error;
+use super::header::{Header, Headers, MAX_HEADERS};
+use super::method::Method;
+use super::path::Path;
+use super::version::HttpVersion;
+use super::{parse_head, Request, MAX_REQUEST_BYTES};
+use crate::parser::{find, RawIter};
+
+const NEWLINE: &[u8] = b"\r\n";
+const HEAD_END: &[u8] = b"\r\n\r\n";
+
+/// The result of feeding bytes to a `RequestParser`.
+// Boxing isn't available without alloc, and the request is returned by value everywhere else.
+#[allow(clippy::large_enum_variant)]
+#[derive(PartialEq, Clone, Debug)]
+pub enum ParseStatus<'a> {
+    /// More bytes are needed before a request can be returned.
+    Incomplete,
+    /// A full request was received.
+    Complete(Request<'a>),
+}
+
+/// Where the parser is within the current request. All offsets are from the start of the buffer.
+#[derive(PartialEq, Copy, Clone, Debug)]
+enum State {
+    /// Looking for the end of the header block. Everything before `scanned` is known not to contain it.
+    Head { scanned: usize },
+    /// Waiting for a fixed length body that ends at `end`.
+    Body { end: usize },
+    /// Waiting for a chunk size line that starts at `start`.
+    ChunkSize { start: usize },
+    /// Waiting for chunk data that starts at `start` and, with its trailing newline, ends at `end`.
+    ChunkData { start: usize, end: usize },
+    /// Waiting for a trailer line, or the final empty line, that starts at `start`.
+    Trailers { start: usize },
+    /// The request occupies the first `len` bytes of the buffer.
+    Complete { len: usize },
+}
+
+/// Where the name and value of a header lie in the buffer.
+#[derive(PartialEq, Copy, Clone, Debug)]
+struct HeaderSpan {
+    name: (usize, usize),
+    value: (usize, usize),
+}
+
+/// Where the body of the request is kept.
+#[derive(PartialEq, Copy, Clone, Debug)]
+enum BodySpan {
+    /// In the buffer, between the offsets.
+    Raw(usize, usize),
+    /// Decoded into the first bytes of the body buffer.
+    Decoded(usize),
+}
+
+/// The parts of the request parsed so far, kept so nothing is parsed twice.
+#[derive(PartialEq, Copy, Clone, Debug)]
+struct Parsed {
+    method: Method,
+    path: Path,
+    version: HttpVersion,
+    headers: [HeaderSpan; MAX_HEADERS],
+    header_count: usize,
+    body: BodySpan,
+}
+
+/// Resumable request parser for data that arrives in pieces, such as reads from a socket.
+/// Bytes are fed in as they arrive and the parser picks up where it left off rather than scanning from the start.
+/// The head is parsed once, when its end arrives, and chunks are decoded as each one completes.
+/// After a request completes, any bytes following it are kept, so pipelined requests can be read by feeding an empty slice.
+pub struct RequestParser {
+    buffer: [u8; MAX_REQUEST_BYTES],
+    body_buffer: [u8; MAX_REQUEST_BYTES],
+    len: usize,
+    state: State,
+    parsed: Option<Parsed>,
+}
+
+impl RequestParser {
+    pub fn new() -> Self {
+        Self {
+            buffer: [0; MAX_REQUEST_BYTES],
+            body_buffer: [0; MAX_REQUEST_BYTES],
+            len: 0,
+            state: State::Head { scanned: 0 },
+            parsed: None,
+        }
+    }
+
+    /// Appends the bytes to the parser and attempts to complete the next request.
+    /// Once an error is returned the connection should be considered unusable.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<ParseStatus<'_>, Error> {
+        // Drop the previously returned request, keeping anything pipelined after it.
+        if let State::Complete { len } = self.state {
+            self.buffer.copy_within(len..self.len, 0);
+            self.len -= len;
+            self.state = State::Head { scanned: 0 };
+            self.parsed = None;
+        }
+
+        if self.len + bytes.len() > MAX_REQUEST_BYTES {
+            return Err(Error::RequestSizeExceeded {
+                max_bytes: MAX_REQUEST_BYTES,
+                bytes: self.len + bytes.len(),
+            });
+        }
+
+        self.buffer[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+
+        while self.advance()? {
+            if let State::Complete { .. } = self.state {
+                return Ok(ParseStatus::Complete(self.request()?));
+            }
+        }
+
+        Ok(ParseStatus::Incomplete)
+    }
+
+    /// Returns the number of bytes received but not yet returned as part of a request.
+    pub fn buffered(&self) -> usize {
+        match self.state {
+            State::Complete { len } => self.len - len,
+            _ => self.len,
+        }
+    }
+
+    /// Builds the completed request from what was kept while parsing it.
+    fn request(&self) -> Result<Request<'_>, Error> {
+        let parsed = self.parsed.as_ref().ok_or(Error::TruncatedBody)?;
+        let text = |(start, end): (usize, usize)| {
+            // The bytes were checked when the head was parsed; this only views them as text again.
+            core::str::from_utf8(&self.buffer[start..end]).map_err(|_| Error::MalformedHeader)
+        };
+
+        let mut headers = Headers::new();
+        for span in &parsed.headers[..parsed.header_count] {
+            headers.push(Header::new(text(span.name)?, text(span.value)?))?;
+        }
+
+        let body = match parsed.body {
+            BodySpan::Raw(start, end) => &self.buffer[start..end],
+            BodySpan::Decoded(len) => &self.body_buffer[..len],
+        };
+
+        Ok(Request {
+            method: parsed.method,
+            path: parsed.path,
+            version: parsed.version,
+            headers,
+            body,
+        })
+    }
+
+    /// Parses the head that ends at `head_len`, keeping where each part of it lies.
+    fn parse_head(&mut self, head_len: usize) -> Result<BodyLength, Error> {
+        let data = &self.buffer[..head_len];
+        let head = parse_head(&mut RawIter::new(data))?;
+        let offset = |text: &str| text.as_ptr() as usize - data.as_ptr() as usize;
+
+        let mut parsed = Parsed {
+            method: head.method,
+            path: head.path,
+            version: head.version,
+            headers: [HeaderSpan {
+                name: (0, 0),
+                value: (0, 0),
+            }; MAX_HEADERS],
+            header_count: head.headers.len(),
+            body: BodySpan::Raw(head_len, head_len),
+        };
+        for (span, header) in parsed.headers.iter_mut().zip(head.headers.iter()) {
+            let (name, value) = (offset(header.name()), offset(header.value()));
+            span.name = (name, name + header.name().len());
+            span.value = (value, value + header.value().len());
+        }
+
+        let length = body_length(&head.headers)?;
+        if length == BodyLength::Chunked {
+            parsed.body = BodySpan::Decoded(0);
+        }
+        self.parsed = Some(parsed);
+        Ok(length)
+    }
+
+    /// Attempts to move to the next state, returning `false` if more data is needed.
+    fn advance(&mut self) -> Result<bool, Error> {
+        let data = &self.buffer[..self.len];
+
+        let state = match self.state {
+            State::Head { scanned } => {
+                // Back up in case the end of the head was split between feeds.
+                let from = scanned.saturating_sub(HEAD_END.len() - 1);
+                let head_len = match find(&data[from..], HEAD_END) {
+                    Some(end) => from + end + HEAD_END.len(),
+                    None => {
+                        self.state = State::Head {
+                            scanned: data.len(),
+                        };
+                        return Ok(false);
+                    }
+                };
+
+                match self.parse_head(head_len)? {
+                    BodyLength::None => State::Complete { len: head_len },
+                    BodyLength::Fixed(len) => {
+                        if let Some(parsed) = self.parsed.as_mut() {
+                            parsed.body = BodySpan::Raw(head_len, head_len + len);
+                        }
+                        State::Body {
+                            end: head_len + len,
+                        }
+                    }
+                    BodyLength::Chunked => State::ChunkSize { start: head_len },
+                }
+            }
+            State::Body { end } => {
+                if data.len() < end {
+                    return Ok(false);
+                }
+
+                State::Complete { len: end }
+            }
+            State::ChunkSize { start } => {
+                let mut iter = RawIter::new(&data[start..]);
+                let size = match parse_chunk_size(&mut iter, MAX_REQUEST_BYTES) {
+                    Ok(size) => size,
+                    Err(Error::TruncatedBody) => return Ok(false),
+                    Err(e) => return Err(e),
+                };
+
+                if size > MAX_REQUEST_BYTES {
+                    return Err(Error::BodySizeExceeded {
+                        max_bytes: MAX_REQUEST_BYTES,
+                        bytes: size,
+                    });
+                }
+
+                let data_start = start + iter.index();
+                if size == 0 {
+                    State::Trailers { start: data_start }
+                } else {
+                    State::ChunkData {
+                        start: data_start,
+                        end: data_start + size + NEWLINE.len(),
+                    }
+                }
+            }
+            State::ChunkData { start, end } => {
+                if data.len() < end {
+                    return Ok(false);
+                }
+
+                if &data[end - NEWLINE.len()..end] != NEWLINE {
+                    return Err(Error::MalformedChunk);
+                }
+
+                // Decode the chunk now so it isn't looked at again.
+                if let Some(Parsed {
+                    body: BodySpan::Decoded(len),
+                    ..
+                }) = self.parsed.as_mut()
+                {
+                    let size = end - NEWLINE.len() - start;
+                    self.body_buffer[*len..*len + size]
+                        .copy_from_slice(&self.buffer[start..start + size]);
+                    *len += size;
+                }
+
+                State::ChunkSize { start: end }
+            }
+            State::Trailers { start } => match find(&data[start..], NEWLINE) {
+                Some(0) => State::Complete {
+                    len: start + NEWLINE.len(),
+                },
+                Some(end) => State::Trailers {
+                    start: start + end + NEWLINE.len(),
+                },
+                None => return Ok(false),
+            },
+            State::Complete { .. } => return Ok(false),
+        };
+
+        self.state = state;
+        Ok(true)
+    }
+}
+
+impl Default for RequestParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::request::Method;
+
+    fn complete<'a>(status: Result<ParseStatus<'a>, Error>) -> Request<'a> {
+        match status {
+            Ok(ParseStatus::Complete(request)) => request,
+            other => panic!("expected a complete request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn feed_whole_request_returns_complete() {
+        let mut parser = RequestParser::new();
+        let request = complete(parser.feed(b"GET /a HTTP/1.1\r\nHost: x\r\n\r\n"));
+
+        assert_eq!(Method::Get, request.method());
+        assert_eq!("/a", request.path().to_str());
+        assert_eq!(Some("x"), request.header("Host"));
+    }
+
+    #[test]
+    fn feed_partial_request_returns_incomplete() {
+        let mut parser = RequestParser::new();
+
+        assert_eq!(Ok(ParseStatus::Incomplete), parser.feed(b"GET /a HT"));
+        assert_eq!(
+            Ok(ParseStatus::Incomplete),
+            parser.feed(b"TP/1.1\r\nHost: x\r\n")
+        );
+        assert_eq!(Ok(ParseStatus::Incomplete), parser.feed(b"\r"));
+
+        let request = complete(parser.feed(b"\n"));
+        assert_eq!("/a", request.path().to_str());
+    }
+
+    #[test]
+    fn feed_byte_at_a_time_returns_complete_once() {
+        let raw = b"POST /a HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello";
+        let mut parser = RequestParser::new();
+
+        for byte in &raw[..raw.len() - 1] {
+            assert_eq!(
+                Ok(ParseStatus::Incomplete),
+                parser.feed(core::slice::from_ref(byte))
+            );
+        }
+
+        let request = complete(parser.feed(&raw[raw.len() - 1..]));
+        assert_eq!(b"hello", request.body());
+    }
+
+    #[test]
+    fn feed_chunked_body_in_pieces_returns_complete() {
+        let mut parser = RequestParser::new();
+
+        assert_eq!(
+            Ok(ParseStatus::Incomplete),
+            parser.feed(b"PUT /a HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n4\r")
+        );
+        assert_eq!(Ok(ParseStatus::Incomplete), parser.feed(b"\nWi"));
+        assert_eq!(
+            Ok(ParseStatus::Incomplete),
+            parser.feed(b"ki\r\n5\r\npedia\r\n")
+        );
+        // Each chunk is decoded as soon as it is complete.
+        assert_eq!(
+            Some(BodySpan::Decoded(9)),
+            parser.parsed.map(|parsed| parsed.body)
+        );
+        assert_eq!(
+            Ok(ParseStatus::Incomplete),
+            parser.feed(b"0\r\nX-Trailer: 1\r\n")
+        );
+
+        let request = complete(parser.feed(b"\r\n"));
+        assert_eq!(b"Wikipedia", request.body());
+        assert_eq!(None, request.header("X-Trailer"));
+    }
+
+    #[test]
+    fn feed_pipelined_requests_returns_each_in_order() {
+        let mut parser = RequestParser::new();
+
+        let first = complete(parser.feed(
+            b"GET /first HTTP/1.1\r\n\r\nPOST /second HTTP/1.1\r\nContent-Length: 2\r\n\r\nhiGET /th",
+        ));
+        assert_eq!("/first", first.path().to_str());
+
+        let second = complete(parser.feed(&[]));
+        assert_eq!(Method::Post, second.method());
+        assert_eq!("/second", second.path().to_str());
+        assert_eq!(b"hi", second.body());
+
+        assert_eq!(Ok(ParseStatus::Incomplete), parser.feed(&[]));
+        assert_eq!(7, parser.buffered());
+
+        let third = complete(parser.feed(b"ird HTTP/1.1\r\n\r\n"));
+        assert_eq!("/third", third.path().to_str());
+        assert_eq!(0, parser.buffered());
+    }
+
+    #[test]
+    fn feed_malformed_chunk_returns_err() {
+        let mut parser = RequestParser::new();
+        let status =
+            parser.feed(b"PUT /a HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nhiX\r\n");
+
+        assert_eq!(Err(Error::MalformedChunk), status);
+    }
+
+    #[test]
+    fn feed_malformed_header_returns_err() {
+        let mut parser = RequestParser::new();
+        let status = parser.feed(b"GET /a HTTP/1.1\r\nHost\r\n\r\n");
+
+        assert_eq!(Err(Error::MalformedHeader), status);
+    }
+
+    #[test]
+    fn feed_past_max_request_bytes_returns_err() {
+        let mut parser = RequestParser::new();
+        assert_eq!(
+            Ok(ParseStatus::Incomplete),
+            parser.feed(b"GET /a HTTP/1.1\r\n")
+        );
+
+        let status = parser.feed(&[b'a'; MAX_REQUEST_BYTES]);
+        assert_eq!(
+            Err(Error::RequestSizeExceeded {
+                max_bytes: MAX_REQUEST_BYTES,
+                bytes: MAX_REQUEST_BYTES + 17
+            }),
+            status
+        );
+    }
+}
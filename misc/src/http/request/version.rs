@@ -0,0 +1,89 @@
+use super::error::Error;
+use crate::parser::RawIter;
+
+/// The HTTP version given on the request line.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum HttpVersion {
+    Http10,
+    Http11,
+}
+
+impl HttpVersion {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            HttpVersion::Http10 => "HTTP/1.0",
+            HttpVersion::Http11 => "HTTP/1.1",
+        }
+    }
+}
+
+const HTTP_NAME: &[u8] = b"HTTP/";
+
+/// Parses an `HTTP/x.y` version, advancing past it.
+pub fn parse_version(request: &mut RawIter) -> Result<HttpVersion, Error> {
+    let data = request.data();
+    if !data.starts_with(HTTP_NAME) {
+        return Err(Error::InvalidHttpVersion);
+    }
+
+    let version = match &data[HTTP_NAME.len()..] {
+        [major, b'.', minor, ..] if major.is_ascii_digit() && minor.is_ascii_digit() => {
+            (*major, *minor)
+        }
+        _ => return Err(Error::InvalidHttpVersion),
+    };
+
+    let version = match version {
+        (b'1', b'0') => HttpVersion::Http10,
+        (b'1', b'1') => HttpVersion::Http11,
+        _ => return Err(Error::UnsupportedHttpVersion),
+    };
+
+    request.advance(HTTP_NAME.len() + 3);
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_version_http11_returns_ok() {
+        let mut request = RawIter::new(b"HTTP/1.1\r\n");
+        assert_eq!(Ok(HttpVersion::Http11), parse_version(&mut request));
+        assert_eq!(b"HTTP/1.1".len(), request.index());
+    }
+
+    #[test]
+    fn parse_version_http10_returns_ok() {
+        let mut request = RawIter::new(b"HTTP/1.0\r\n");
+        assert_eq!(Ok(HttpVersion::Http10), parse_version(&mut request));
+        assert_eq!(b"HTTP/1.0".len(), request.index());
+    }
+
+    #[test]
+    fn parse_version_unsupported_returns_err() {
+        let mut request = RawIter::new(b"HTTP/2.0\r\n");
+        assert_eq!(
+            Err(Error::UnsupportedHttpVersion),
+            parse_version(&mut request)
+        );
+        assert_eq!(0, request.index());
+    }
+
+    #[test]
+    fn parse_version_malformed_returns_err() {
+        for raw in [&b"HTTP/1\r\n"[..], b"http/1.1\r\n", b"HTTP/a.b\r\n", b""].iter() {
+            let mut request = RawIter::new(raw);
+            assert_eq!(Err(Error::InvalidHttpVersion), parse_version(&mut request));
+        }
+    }
+
+    #[test]
+    fn to_str_round_trips() {
+        for version in [HttpVersion::Http10, HttpVersion::Http11].iter() {
+            let mut request = RawIter::new(version.to_str().as_bytes());
+            assert_eq!(Ok(*version), parse_version(&mut request));
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use super::Header;
+
+/// Size of the scratch buffer a `BodyProducer` is given for each chunk.
+pub const CHUNK_BUFFER_BYTES: usize = 1024;
+
+/// Produces the body of a chunked response one fragment at a time.
+///
+/// Writing a response may run its write passes more than once, to measure it or to resume after backpressure,
+/// so a fragment must always come out the same for the same index.
+pub trait BodyProducer {
+    /// Returns fragment `index` of the body, or `None` once there are no more.
+    /// The fragment may be borrowed from the producer or written into `buffer`, which holds `CHUNK_BUFFER_BYTES`.
+    /// Empty fragments are skipped, as an empty chunk would end the body.
+    fn chunk<'s>(&'s self, index: usize, buffer: &'s mut [u8]) -> Option<&'s [u8]>;
+
+    /// The length of every fragment but the last, for producers whose fragments are all the same size.
+    /// Lets a range of the body be sent starting at the fragment that holds it, rather than producing every fragment before it.
+    fn fragment_len(&self) -> Option<usize> {
+        None
+    }
+
+    /// Trailer fields sent after the last chunk.
+    fn trailers(&self) -> &[Header<'_>] {
+        &[]
+    }
+}
+
+/// A body made of fragments that already exist, sent as one chunk each.
+#[derive(Copy, Clone, Debug)]
+pub struct Fragments<'f> {
+    fragments: &'f [&'f [u8]],
+    trailers: &'f [Header<'f>],
+}
+
+impl<'f> Fragments<'f> {
+    pub fn new(fragments: &'f [&'f [u8]]) -> Self {
+        Self {
+            fragments,
+            trailers: &[],
+        }
+    }
+
+    pub fn with_trailers(mut self, trailers: &'f [Header<'f>]) -> Self {
+        self.trailers = trailers;
+        self
+    }
+}
+
+impl<'f> BodyProducer for Fragments<'f> {
+    fn chunk<'s>(&'s self, index: usize, _buffer: &'s mut [u8]) -> Option<&'s [u8]> {
+        self.fragments.get(index).copied()
+    }
+
+    fn trailers(&self) -> &[Header<'_>] {
+        self.trailers
+    }
+}
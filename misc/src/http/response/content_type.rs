@@ -1,21 +1,181 @@
-#[derive(PartialEq, Copy, Clone, Debug)]
-pub enum ContentType {
-    Json,
-    Html,
+macro_rules! content_types {
+    ($(($variant:ident, $value:expr, [$($extension:expr),*])),* $(,)?) => {
+        #[derive(PartialEq, Copy, Clone, Debug)]
+        pub enum ContentType {
+            $($variant,)*
+        }
+
+        impl ContentType {
+            /// The MIME type, without any parameters.
+            pub fn to_header_value(&self) -> &'static str {
+                match self {
+                    $(ContentType::$variant => $value,)*
+                }
+            }
+
+            /// Parses the MIME type from a `Content-Type` header value, ignoring any parameters.
+            /// Returns `None` for types that aren't known.
+            pub fn parse(value: &str) -> Option<Self> {
+                let essence = value.split(';').next().unwrap_or("").trim();
+                $(
+                    if essence.eq_ignore_ascii_case($value) {
+                        return Some(ContentType::$variant);
+                    }
+                )*
+                None
+            }
+
+            /// Returns the type usually served for files with the given extension, such as `wasm` or `html`. The lookup is case-insensitive.
+            pub fn from_extension(extension: &str) -> Option<Self> {
+                $($(
+                    if extension.eq_ignore_ascii_case($extension) {
+                        return Some(ContentType::$variant);
+                    }
+                )*)*
+                None
+            }
+        }
+    };
 }
 
+content_types!(
+    (Json, "application/json", ["json"]),
+    (Html, "text/html", ["html", "htm"]),
+    (PlainText, "text/plain", ["txt"]),
+    (Css, "text/css", ["css"]),
+    (JavaScript, "text/javascript", ["js", "mjs"]),
+    (Wasm, "application/wasm", ["wasm"]),
+    (Svg, "image/svg+xml", ["svg"]),
+    (Png, "image/png", ["png"]),
+    (OctetStream, "application/octet-stream", ["bin"]),
+    (FormUrlEncoded, "application/x-www-form-urlencoded", []),
+    (Multipart, "multipart/form-data", []),
+);
+
 impl ContentType {
-    pub fn to_header_value(&self) -> &'static str {
-        match self {
-            ContentType::Json => "application/json",
-            ContentType::Html => "text/html",
+    /// Whether the type is text, and so should normally be sent with a charset.
+    pub fn is_text(&self) -> bool {
+        matches!(
+            self,
+            ContentType::Html
+                | ContentType::PlainText
+                | ContentType::Css
+                | ContentType::JavaScript
+                | ContentType::Svg
+        )
+    }
+
+    pub fn with_charset(self, charset: &str) -> MediaType<'_> {
+        MediaType {
+            content_type: self,
+            charset: Some(charset),
+            boundary: None,
+        }
+    }
+
+    pub fn with_boundary(self, boundary: &str) -> MediaType<'_> {
+        MediaType {
+            content_type: self,
+            charset: None,
+            boundary: Some(boundary),
+        }
+    }
+}
+
+/// A content type along with the `charset` and `boundary` parameters of a `Content-Type` header.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct MediaType<'a> {
+    content_type: ContentType,
+    charset: Option<&'a str>,
+    boundary: Option<&'a str>,
+}
+
+impl<'a> MediaType<'a> {
+    pub fn new(content_type: ContentType) -> Self {
+        Self {
+            content_type,
+            charset: None,
+            boundary: None,
+        }
+    }
+
+    /// Parses a `Content-Type` header value, such as `text/html; charset=utf-8`. Unknown parameters are ignored.
+    pub fn parse(value: &'a str) -> Option<Self> {
+        let mut parts = value.split(';');
+        let content_type = ContentType::parse(parts.next().unwrap_or(""))?;
+        let mut media_type = Self::new(content_type);
+
+        for param in parts {
+            let (key, value) = match param.find('=') {
+                Some(index) => (param[..index].trim(), param[index + 1..].trim()),
+                None => continue,
+            };
+
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+
+            if key.eq_ignore_ascii_case("charset") {
+                media_type.charset = Some(value);
+            } else if key.eq_ignore_ascii_case("boundary") {
+                media_type.boundary = Some(value);
+            }
+        }
+
+        Some(media_type)
+    }
+
+    pub fn content_type(&self) -> ContentType {
+        self.content_type
+    }
+
+    pub fn charset(&self) -> Option<&'a str> {
+        self.charset
+    }
+
+    /// The boundary separating the parts of a multipart body.
+    pub fn boundary(&self) -> Option<&'a str> {
+        self.boundary
+    }
+}
+
+/// Writes the header value, such as `text/html; charset=utf-8`.
+impl<'a> core::fmt::Display for MediaType<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.content_type.to_header_value())?;
+
+        if let Some(charset) = self.charset {
+            write!(f, "; charset={}", charset)?;
         }
+
+        if let Some(boundary) = self.boundary {
+            // Boundaries may contain characters that are only allowed inside quotes.
+            if boundary
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"-_.".contains(&b))
+            {
+                write!(f, "; boundary={}", boundary)?;
+            } else {
+                write!(f, "; boundary=\"{}\"", boundary)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<ContentType> for MediaType<'_> {
+    fn from(content_type: ContentType) -> Self {
+        Self::new(content_type)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    extern crate std;
     use super::*;
+    use std::string::ToString;
 
     #[test]
     fn json() {
@@ -28,4 +188,93 @@ mod tests {
         let content = ContentType::Html;
         assert_eq!("text/html", content.to_header_value());
     }
+
+    #[test]
+    fn parse_round_trips() {
+        let types = [
+            ContentType::Json,
+            ContentType::Html,
+            ContentType::PlainText,
+            ContentType::Css,
+            ContentType::JavaScript,
+            ContentType::Wasm,
+            ContentType::Svg,
+            ContentType::Png,
+            ContentType::OctetStream,
+            ContentType::FormUrlEncoded,
+            ContentType::Multipart,
+        ];
+
+        for content in types.iter() {
+            assert_eq!(
+                Some(*content),
+                ContentType::parse(content.to_header_value())
+            );
+        }
+    }
+
+    #[test]
+    fn parse_ignores_case_and_parameters() {
+        assert_eq!(
+            Some(ContentType::Html),
+            ContentType::parse(" Text/HTML ; charset=utf-8")
+        );
+    }
+
+    #[test]
+    fn parse_unknown_returns_none() {
+        assert_eq!(None, ContentType::parse("image/jpeg"));
+        assert_eq!(None, ContentType::parse(""));
+    }
+
+    #[test]
+    fn from_extension() {
+        assert_eq!(Some(ContentType::Wasm), ContentType::from_extension("wasm"));
+        assert_eq!(Some(ContentType::Html), ContentType::from_extension("HTM"));
+        assert_eq!(
+            Some(ContentType::JavaScript),
+            ContentType::from_extension("mjs")
+        );
+        assert_eq!(None, ContentType::from_extension("exe"));
+    }
+
+    #[test]
+    fn media_type_parse_reads_charset() {
+        let media_type = MediaType::parse("text/plain; charset=\"UTF-8\"").unwrap();
+        assert_eq!(ContentType::PlainText, media_type.content_type());
+        assert_eq!(Some("UTF-8"), media_type.charset());
+        assert_eq!(None, media_type.boundary());
+    }
+
+    #[test]
+    fn media_type_parse_reads_boundary() {
+        let media_type =
+            MediaType::parse("multipart/form-data; boundary=----WebKitFormBoundary7MA4YWxk")
+                .unwrap();
+        assert_eq!(ContentType::Multipart, media_type.content_type());
+        assert_eq!(
+            Some("----WebKitFormBoundary7MA4YWxk"),
+            media_type.boundary()
+        );
+    }
+
+    #[test]
+    fn media_type_to_string() {
+        assert_eq!(
+            "text/html; charset=utf-8",
+            ContentType::Html.with_charset("utf-8").to_string()
+        );
+        assert_eq!(
+            "multipart/form-data; boundary=abc",
+            ContentType::Multipart.with_boundary("abc").to_string()
+        );
+        assert_eq!(
+            "multipart/form-data; boundary=\"a b\"",
+            ContentType::Multipart.with_boundary("a b").to_string()
+        );
+        assert_eq!(
+            "application/json",
+            MediaType::from(ContentType::Json).to_string()
+        );
+    }
 }
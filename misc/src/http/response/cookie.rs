@@ -0,0 +1,177 @@
+use crate::http::date::HttpDate;
+
+/// Whether the browser sends the cookie with cross-site requests.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    /// Sent with all requests. Browsers only accept this together with `Secure`.
+    None,
+}
+
+impl SameSite {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+/// A `Set-Cookie` header value. Display writes it as it is sent, such as `id=a3fW; Path=/; HttpOnly`.
+/// The name and value are written as given, so they must already be valid cookie octets.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct SetCookie<'a> {
+    name: &'a str,
+    value: &'a str,
+    path: Option<&'a str>,
+    domain: Option<&'a str>,
+    max_age: Option<u64>,
+    expires: Option<HttpDate>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+}
+
+impl<'a> SetCookie<'a> {
+    pub fn new(name: &'a str, value: &'a str) -> Self {
+        Self {
+            name,
+            value,
+            path: None,
+            domain: None,
+            max_age: None,
+            expires: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        }
+    }
+
+    /// A cookie that removes `name` from the browser, by expiring it straight away.
+    pub fn removal(name: &'a str) -> Self {
+        Self::new(name, "")
+            .with_max_age(0)
+            .with_expires(HttpDate::from_unix(0))
+    }
+
+    pub fn with_path(mut self, path: &'a str) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    pub fn with_domain(mut self, domain: &'a str) -> Self {
+        self.domain = Some(domain);
+        self
+    }
+
+    /// The number of seconds until the cookie expires. Takes precedence over `Expires` in browsers that support both.
+    pub fn with_max_age(mut self, seconds: u64) -> Self {
+        self.max_age = Some(seconds);
+        self
+    }
+
+    pub fn with_expires(mut self, expires: HttpDate) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    /// Only send the cookie over HTTPS.
+    pub fn with_secure(mut self) -> Self {
+        self.secure = true;
+        self
+    }
+
+    /// Hide the cookie from scripts.
+    pub fn with_http_only(mut self) -> Self {
+        self.http_only = true;
+        self
+    }
+
+    pub fn with_same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn value(&self) -> &'a str {
+        self.value
+    }
+}
+
+impl<'a> core::fmt::Display for SetCookie<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+
+        if let Some(path) = self.path {
+            write!(f, "; Path={}", path)?;
+        }
+
+        if let Some(domain) = self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age)?;
+        }
+
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={}", expires)?;
+        }
+
+        if self.secure {
+            f.write_str("; Secure")?;
+        }
+
+        if self.http_only {
+            f.write_str("; HttpOnly")?;
+        }
+
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={}", same_site.to_str())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use std::string::ToString;
+
+    #[test]
+    fn to_string_writes_name_and_value() {
+        assert_eq!("id=a3fW", SetCookie::new("id", "a3fW").to_string());
+    }
+
+    #[test]
+    fn to_string_writes_attributes_in_order() {
+        let cookie = SetCookie::new("session", "abc")
+            .with_path("/")
+            .with_domain("example.com")
+            .with_max_age(3600)
+            .with_expires(HttpDate::from_unix(784_111_777))
+            .with_secure()
+            .with_http_only()
+            .with_same_site(SameSite::Lax);
+
+        assert_eq!(
+            "session=abc; Path=/; Domain=example.com; Max-Age=3600; Expires=Sun, 06 Nov 1994 08:49:37 GMT; Secure; HttpOnly; SameSite=Lax",
+            cookie.to_string()
+        );
+    }
+
+    #[test]
+    fn removal_expires_immediately() {
+        assert_eq!(
+            "theme=; Max-Age=0; Expires=Thu, 01 Jan 1970 00:00:00 GMT",
+            SetCookie::removal("theme").to_string()
+        );
+    }
+}
@@ -0,0 +1,29 @@
+use crate::http::request::Error as RequestError;
+
+/// Errors from parsing a response.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Error {
+    ExpectedSpace,
+    ExpectedNewline,
+    /// The status code wasn't three digits.
+    InvalidStatusCode,
+    /// The reason phrase contained control characters.
+    InvalidReasonPhrase,
+    InvalidContentLength,
+    /// Several `Content-Length` headers disagreed.
+    ConflictingBodyLength,
+    MalformedChunk,
+    TruncatedBody,
+    BodyBufferTooSmall {
+        max_bytes: usize,
+        bytes: usize,
+    },
+    /// The version or headers failed to parse. These are shared with requests and so use the request errors.
+    Message(RequestError),
+}
+
+impl From<RequestError> for Error {
+    fn from(error: RequestError) -> Self {
+        Error::Message(error)
+    }
+}
@@ -0,0 +1,341 @@
+use super::ResponseError;
+use core::fmt::Write;
+
+pub const HEADER_COUNT: usize = 100;
+/// Space shared by all header values written with the formatted header methods.
+pub const HEADER_BUFFER_BYTES: usize = 1024;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Header<'a> {
+    pub key: &'a str,
+    pub value: &'a str,
+}
+
+/// A header value either borrowed from the caller or written into the header buffer.
+#[derive(Copy, Clone, Debug)]
+enum HeaderValue<'a> {
+    Borrowed(&'a str),
+    Formatted { start: usize, len: usize },
+}
+
+#[derive(Copy, Clone, Debug)]
+struct StoredHeader<'a> {
+    key: &'a str,
+    value: HeaderValue<'a>,
+}
+
+/// The headers of an outgoing message, kept in insertion order. Keys are compared case-insensitively.
+/// Space used by a formatted value is not reclaimed when its header is replaced or removed.
+pub struct HeaderTable<'a> {
+    headers: [Option<StoredHeader<'a>>; HEADER_COUNT],
+    len: usize,
+    buffer: [u8; HEADER_BUFFER_BYTES],
+    buffer_len: usize,
+}
+
+impl<'a> HeaderTable<'a> {
+    pub fn new() -> Self {
+        Self {
+            headers: [None; HEADER_COUNT],
+            len: 0,
+            buffer: [0; HEADER_BUFFER_BYTES],
+            buffer_len: 0,
+        }
+    }
+
+    /// Adds the header after all existing ones, even if one with the same key exists.
+    pub fn append(&mut self, header: Header<'a>) -> Result<(), ResponseError> {
+        self.push(StoredHeader {
+            key: header.key,
+            value: HeaderValue::Borrowed(header.value),
+        })
+    }
+
+    /// Replaces the first header with the same key, removing any others. If there is none the header is appended.
+    pub fn set(&mut self, header: Header<'a>) -> Result<(), ResponseError> {
+        self.replace(StoredHeader {
+            key: header.key,
+            value: HeaderValue::Borrowed(header.value),
+        })
+    }
+
+    /// Like `append`, but the value is formatted into the header buffer.
+    pub fn append_formatted(
+        &mut self,
+        key: &'a str,
+        value: core::fmt::Arguments,
+    ) -> Result<(), ResponseError> {
+        let value = self.format(value)?;
+        self.push(StoredHeader { key, value })?;
+        self.commit(value);
+        Ok(())
+    }
+
+    /// Like `set`, but the value is formatted into the header buffer.
+    pub fn set_formatted(
+        &mut self,
+        key: &'a str,
+        value: core::fmt::Arguments,
+    ) -> Result<(), ResponseError> {
+        let value = self.format(value)?;
+        self.replace(StoredHeader { key, value })?;
+        self.commit(value);
+        Ok(())
+    }
+
+    /// Removes every header with the key, returning whether any were removed.
+    pub fn remove(&mut self, key: &str) -> bool {
+        self.retain(|_, header| !header.key.eq_ignore_ascii_case(key))
+    }
+
+    /// Returns the value of the first header with the key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Iterates over the `(key, value)` pairs in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.headers[..self.len]
+            .iter()
+            .flatten()
+            .map(move |header| (header.key, self.value(&header.value)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn value(&self, value: &HeaderValue<'a>) -> &str {
+        match value {
+            HeaderValue::Borrowed(value) => value,
+            // Formatted values are only ever written from a `str`.
+            HeaderValue::Formatted { start, len } => {
+                core::str::from_utf8(&self.buffer[*start..*start + *len]).unwrap()
+            }
+        }
+    }
+
+    fn push(&mut self, header: StoredHeader<'a>) -> Result<(), ResponseError> {
+        if self.len >= HEADER_COUNT {
+            return Err(ResponseError::HeaderLengthExceeded {
+                max_headers: HEADER_COUNT,
+            });
+        }
+
+        self.headers[self.len] = Some(header);
+        self.len += 1;
+        Ok(())
+    }
+
+    fn replace(&mut self, header: StoredHeader<'a>) -> Result<(), ResponseError> {
+        let existing = self.headers[..self.len]
+            .iter()
+            .flatten()
+            .position(|h| h.key.eq_ignore_ascii_case(header.key));
+
+        match existing {
+            Some(index) => {
+                self.headers[index] = Some(header);
+                self.retain(|i, h| i <= index || !h.key.eq_ignore_ascii_case(header.key));
+                Ok(())
+            }
+            None => self.push(header),
+        }
+    }
+
+    /// Keeps only the headers matching the predicate, preserving their order. Returns whether any were dropped.
+    fn retain(&mut self, keep: impl Fn(usize, &StoredHeader<'a>) -> bool) -> bool {
+        let mut kept = 0;
+        for i in 0..self.len {
+            let header = self.headers[i];
+            if let Some(h) = &header {
+                if !keep(i, h) {
+                    continue;
+                }
+            }
+
+            self.headers[kept] = header;
+            kept += 1;
+        }
+
+        for header in &mut self.headers[kept..self.len] {
+            *header = None;
+        }
+
+        let removed = kept != self.len;
+        self.len = kept;
+        removed
+    }
+
+    /// Writes the value into the free part of the buffer without claiming the space.
+    fn format(&mut self, value: core::fmt::Arguments) -> Result<HeaderValue<'a>, ResponseError> {
+        let start = self.buffer_len;
+        let mut writer = HeaderWriter {
+            buffer: &mut self.buffer[start..],
+            len: 0,
+        };
+
+        if writer.write_fmt(value).is_err() {
+            return Err(ResponseError::HeaderBufferExceeded {
+                max_bytes: HEADER_BUFFER_BYTES,
+            });
+        }
+
+        Ok(HeaderValue::Formatted {
+            start,
+            len: writer.len,
+        })
+    }
+
+    /// Claims the buffer space used by a formatted value once its header has been stored.
+    fn commit(&mut self, value: HeaderValue<'a>) {
+        if let HeaderValue::Formatted { len, .. } = value {
+            self.buffer_len += len;
+        }
+    }
+}
+
+impl<'a> Default for HeaderTable<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes formatted header values into the unused part of the header buffer.
+struct HeaderWriter<'b> {
+    buffer: &'b mut [u8],
+    len: usize,
+}
+
+impl<'b> Write for HeaderWriter<'b> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buffer.len() {
+            return Err(core::fmt::Error);
+        }
+
+        self.buffer[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys<'h>(headers: &'h HeaderTable) -> [&'h str; 4] {
+        let mut keys = [""; 4];
+        for (i, (key, _)) in headers.iter().enumerate().take(4) {
+            keys[i] = key;
+        }
+
+        keys
+    }
+
+    fn header<'a>(key: &'a str, value: &'a str) -> Header<'a> {
+        Header { key, value }
+    }
+
+    #[test]
+    fn append_keeps_insertion_order() {
+        let mut headers = HeaderTable::new();
+        headers.append(header("A", "1")).unwrap();
+        headers.append(header("B", "2")).unwrap();
+        headers.append(header("C", "3")).unwrap();
+
+        assert_eq!(["A", "B", "C", ""], keys(&headers));
+    }
+
+    #[test]
+    fn append_allows_duplicates() {
+        let mut headers = HeaderTable::new();
+        headers.append(header("Set-Cookie", "a=1")).unwrap();
+        headers.append(header("Set-Cookie", "b=2")).unwrap();
+
+        assert_eq!(2, headers.len());
+        assert_eq!(Some("a=1"), headers.get("set-cookie"));
+    }
+
+    #[test]
+    fn set_replaces_in_place_case_insensitively() {
+        let mut headers = HeaderTable::new();
+        headers.append(header("A", "1")).unwrap();
+        headers.append(header("Cache-Control", "no-cache")).unwrap();
+        headers.append(header("C", "3")).unwrap();
+        headers.append(header("cache-control", "private")).unwrap();
+
+        headers.set(header("CACHE-CONTROL", "max-age=60")).unwrap();
+
+        assert_eq!(["A", "CACHE-CONTROL", "C", ""], keys(&headers));
+        assert_eq!(Some("max-age=60"), headers.get("Cache-Control"));
+    }
+
+    #[test]
+    fn set_without_existing_appends() {
+        let mut headers = HeaderTable::new();
+        headers.append(header("A", "1")).unwrap();
+        headers.set(header("B", "2")).unwrap();
+
+        assert_eq!(["A", "B", "", ""], keys(&headers));
+    }
+
+    #[test]
+    fn remove_removes_all_matches_and_keeps_order() {
+        let mut headers = HeaderTable::new();
+        headers.append(header("A", "1")).unwrap();
+        headers.append(header("X", "1")).unwrap();
+        headers.append(header("B", "2")).unwrap();
+        headers.append(header("x", "2")).unwrap();
+        headers.append(header("C", "3")).unwrap();
+
+        assert!(headers.remove("X"));
+        assert!(!headers.remove("X"));
+        assert_eq!(["A", "B", "C", ""], keys(&headers));
+    }
+
+    #[test]
+    fn get_missing_returns_none() {
+        let headers = HeaderTable::new();
+        assert_eq!(None, headers.get("A"));
+    }
+
+    #[test]
+    fn set_formatted_replaces_value() {
+        let mut headers = HeaderTable::new();
+        headers
+            .append_formatted("ETag", format_args!("\"{}\"", 1))
+            .unwrap();
+        headers
+            .set_formatted("etag", format_args!("\"{}\"", 2))
+            .unwrap();
+
+        assert_eq!(1, headers.len());
+        assert_eq!(Some("\"2\""), headers.get("ETag"));
+    }
+
+    #[test]
+    fn append_past_capacity_returns_err() {
+        let mut headers = HeaderTable::new();
+        for _ in 0..HEADER_COUNT {
+            headers.append(header("A", "1")).unwrap();
+        }
+
+        assert!(matches!(
+            headers.append(header("A", "1")),
+            Err(ResponseError::HeaderLengthExceeded {
+                max_headers: HEADER_COUNT
+            })
+        ));
+    }
+}
@@ -1,65 +1,303 @@
-use core::{fmt::Write, panic, unimplemented};
-
-use crate::parser::StreamError;
+mod body;
+mod cookie;
+mod error;
+mod parser;
+pub use body::{BodyProducer, Fragments, CHUNK_BUFFER_BYTES};
+pub use cookie::{SameSite, SetCookie};
+pub use error::Error;
+pub use parser::{parse, parse_response_to, parse_with_body_buffer, ParsedResponse};
+mod headers;
+pub use headers::{Header, HeaderTable, HEADER_BUFFER_BYTES, HEADER_COUNT};
+mod writer;
+pub use crate::parser::StreamError;
+#[cfg(feature = "std")]
+pub use writer::IoWriter;
+pub(crate) use writer::PassOutput;
+pub use writer::{BufferedWriter, ResponseWriter, SizeCounter, SliceWriter};
 mod status;
-pub use status::Status;
+pub use status::{CustomStatus, Status, StatusLine};
 mod content_type;
-pub use content_type::ContentType;
+pub use content_type::{ContentType, MediaType};
 
-const HEADER_COUNT: usize = 100;
-
-#[derive(Copy, Clone, Debug)]
-pub struct Header {
-    pub key: &'static str,
-    pub value: &'static str,
-}
+use crate::http::request::{Method, Ranges, MAX_RANGES};
+#[cfg(feature = "std")]
+use std::boxed::Box;
 
 #[derive(Copy, Clone, Debug)]
 pub enum ResponseError {
     HeaderLengthExceeded { max_headers: usize },
+    HeaderBufferExceeded { max_bytes: usize },
+}
+
+/// Where the body of a response comes from.
+enum Body<'a> {
+    /// Sent as is, with a `Content-Length`.
+    Bytes(&'a [u8]),
+    /// Pulled from the producer and sent with `Transfer-Encoding: chunked`.
+    Chunked(&'a dyn BodyProducer),
+    /// Pulled from the producer and sent with a `Content-Length` known up front.
+    Sized(Producer<'a>, usize),
+    /// Pulled from the producer and sent as is, ended by closing the connection.
+    UntilClose(&'a dyn BodyProducer),
+}
+
+/// A producer the response borrows or, with the `std` feature, owns.
+enum Producer<'a> {
+    Borrowed(&'a dyn BodyProducer),
+    #[cfg(feature = "std")]
+    Owned(Box<dyn BodyProducer + 'a>),
+}
+
+impl<'a> Producer<'a> {
+    fn get(&self) -> &dyn BodyProducer {
+        match self {
+            Producer::Borrowed(producer) => *producer,
+            #[cfg(feature = "std")]
+            Producer::Owned(producer) => producer.as_ref(),
+        }
+    }
+}
+
+/// The parts of the body a 206 or 416 response is limited to.
+#[derive(Copy, Clone)]
+struct Ranged<'a> {
+    /// The first and last byte of each satisfiable range, inclusive. None are left for a 416.
+    ranges: [(usize, usize); MAX_RANGES],
+    count: usize,
+    /// The length of the whole body.
+    complete_len: usize,
+    boundary: &'a str,
+}
+
+impl<'a> Ranged<'a> {
+    fn ranges(&self) -> &[(usize, usize)] {
+        &self.ranges[..self.count]
+    }
+
+    fn is_multipart(&self) -> bool {
+        self.count > 1
+    }
 }
 
 pub struct Response<'a> {
     status: Status,
-    body: &'a [u8],
-    headers: [Option<Header>; HEADER_COUNT],
+    method: Method,
+    body: Body<'a>,
+    /// The ranges asked for and the boundary for several, resolved against the body when it is written.
+    ranges: Option<(Ranges, &'a str)>,
+    headers: HeaderTable<'a>,
 }
 
 impl<'a> Response<'a> {
     pub fn new(status: Status) -> Self {
         Self {
             status,
-            body: &[],
-            headers: [None; HEADER_COUNT],
+            method: Method::Get,
+            body: Body::Bytes(&[]),
+            ranges: None,
+            headers: HeaderTable::new(),
         }
     }
 
     pub fn with_body(&mut self, body: &'a [u8]) -> Result<(), ResponseError> {
-        self.body = body;
+        self.body = Body::Bytes(body);
         Ok(())
     }
 
-    pub fn with_header(&mut self, header: Header) -> Result<(), ResponseError> {
-        let mut i = None;
+    /// Sets the method of the request being answered, `GET` by default.
+    /// The response to a `HEAD` request is written with its headers, including `Content-Length`, but without the body.
+    pub fn with_request_method(&mut self, method: Method) {
+        self.method = method;
+    }
 
-        for j in 0..HEADER_COUNT {
-            if self.headers[j].is_none() {
-                i = Some(j);
-            }
+    pub fn request_method(&self) -> Method {
+        self.method
+    }
+
+    /// The status that is written, which is 206 or 416 when the body is limited to ranges.
+    pub fn status(&self) -> Status {
+        match self.ranged() {
+            Some(ranged) if ranged.count == 0 => Status::RangeNotSatisfiable,
+            Some(_) => Status::PartialContent,
+            None => self.status,
+        }
+    }
+
+    /// Whether the status allows a body. 1xx, 204 and 304 responses are written without a body or any `Content-Length` or `Transfer-Encoding`.
+    pub fn allows_body(&self) -> bool {
+        self.status.allows_body()
+    }
+
+    /// Whether body bytes are written, which they aren't for a `HEAD` request or a status that doesn't allow a body.
+    fn writes_body(&self) -> bool {
+        self.allows_body() && self.method != Method::Head
+    }
+
+    /// Sends the body in chunks pulled from the producer, for bodies that are large or generated while writing.
+    /// `Transfer-Encoding: chunked` is written in place of `Content-Length`, and any `Content-Length` or `Transfer-Encoding` headers are left out.
+    /// If the producer has trailers, a `Trailer` header naming them is added unless one was set.
+    pub fn with_chunked_body(&mut self, producer: &'a dyn BodyProducer) {
+        self.body = Body::Chunked(producer);
+    }
+
+    /// Sends `len` bytes pulled from the producer, with a `Content-Length` like `with_body`, for large bodies whose size is known such as files.
+    /// Fragments are cut off once `len` bytes are written, and the producer's trailers aren't sent.
+    /// A producer that runs out early leaves the response shorter than announced, which the client sees as a broken message.
+    pub fn with_sized_body(&mut self, producer: &'a dyn BodyProducer, len: usize) {
+        self.body = Body::Sized(Producer::Borrowed(producer), len);
+    }
+
+    /// Like `with_sized_body`, but the response owns the producer, for one made to answer this request alone such as an open file.
+    /// Only available with the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn with_owned_body(&mut self, producer: Box<dyn BodyProducer + 'a>, len: usize) {
+        self.body = Body::Sized(Producer::Owned(producer), len);
+    }
+
+    /// Sends a chunked body without chunked transfer coding, for clients that don't support it such as HTTP/1.0 ones.
+    /// The fragments are written as they are and the body ends when the connection does, so the connection must be closed after the response.
+    /// No `Content-Length`, `Transfer-Encoding` or `Trailer` header is written, and the producer's trailers aren't sent. Other bodies are left as they are.
+    pub fn delimit_by_close(&mut self) {
+        if let Body::Chunked(producer) = self.body {
+            self.body = Body::UntilClose(producer);
         }
+    }
 
-        match i {
-            Some(i) => {
-                self.headers[i] = Some(header);
+    /// Limits the body to the ranges asked for in a `Range` header.
+    /// The ranges are resolved against the body set when the response is written, so the body may be set before or after.
+    /// * With one satisfiable range, the status becomes 206 and `Content-Range` says which part of the body is sent.
+    /// * With several, the status becomes 206 and the body is sent as `multipart/byteranges`, with parts separated by `boundary`.
+    ///   Each part carries the `Content-Type` header and its own `Content-Range`.
+    /// * With none, the status becomes 416 and the body is left out, with `Content-Range` giving its full length.
+    ///
+    /// Only a 200 response to `GET` with a body of known length, from `with_body`, `with_sized_body` or `with_owned_body`, can be limited.
+    /// For any other the ranges are ignored, which the client takes as the whole body being sent. RFC 9110 section 14.2 has `Range` ignored for other methods, `HEAD` included.
+    pub fn with_ranges(&mut self, ranges: &Ranges, boundary: &'a str) {
+        self.ranges = Some((*ranges, boundary));
+    }
+
+    /// The ranges resolved against the current body, unless the response can't be limited to them.
+    fn ranged(&self) -> Option<Ranged<'a>> {
+        let (ranges, boundary) = self.ranges?;
+        if self.method != Method::Get
+            || self.status != Status::Ok
+            || matches!(self.body, Body::Chunked(_) | Body::UntilClose(_))
+        {
+            return None;
+        }
+
+        let mut ranged = Ranged {
+            ranges: [(0, 0); MAX_RANGES],
+            count: 0,
+            complete_len: self.complete_body_len(),
+            boundary,
+        };
+        for range in ranges.iter() {
+            if let Some((first, last)) = range.resolve(ranged.complete_len as u64) {
+                ranged.ranges[ranged.count] = (first as usize, last as usize);
+                ranged.count += 1;
             }
-            None => {
-                return Err(ResponseError::HeaderLengthExceeded {
-                    max_headers: HEADER_COUNT,
-                })
+        }
+
+        Some(ranged)
+    }
+
+    /// Whether the body is sent with chunked transfer coding.
+    pub fn is_chunked(&self) -> bool {
+        matches!(self.body, Body::Chunked(_))
+    }
+
+    /// Adds the header after any existing ones. Same as `append_header`.
+    pub fn with_header(&mut self, header: Header<'a>) -> Result<(), ResponseError> {
+        self.append_header(header)
+    }
+
+    /// Adds the header after any existing ones, keeping earlier headers with the same key.
+    pub fn append_header(&mut self, header: Header<'a>) -> Result<(), ResponseError> {
+        self.headers.append(header)
+    }
+
+    /// Replaces any headers with the same key, compared case-insensitively, keeping the position of the first.
+    pub fn set_header(&mut self, header: Header<'a>) -> Result<(), ResponseError> {
+        self.headers.set(header)
+    }
+
+    /// Removes all headers with the key, returning whether any were removed.
+    pub fn remove_header(&mut self, key: &str) -> bool {
+        self.headers.remove(key)
+    }
+
+    /// Returns the value of the first header with the key.
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers.get(key)
+    }
+
+    pub fn headers(&self) -> &HeaderTable<'a> {
+        &self.headers
+    }
+
+    /// Adds a header whose value is formatted into the response's own buffer, for values that are computed such as an `ETag` or `Location`.
+    /// Returns `ResponseError::HeaderBufferExceeded` if the value doesn't fit in what is left of the buffer.
+    pub fn with_formatted_header(
+        &mut self,
+        key: &'a str,
+        value: core::fmt::Arguments,
+    ) -> Result<(), ResponseError> {
+        self.headers.append_formatted(key, value)
+    }
+
+    /// Like `set_header`, but the value is formatted into the response's own buffer.
+    pub fn set_formatted_header(
+        &mut self,
+        key: &'a str,
+        value: core::fmt::Arguments,
+    ) -> Result<(), ResponseError> {
+        self.headers.set_formatted(key, value)
+    }
+
+    /// Adds a `Set-Cookie` header for the cookie. Each cookie gets its own header, after any existing ones.
+    /// The header is formatted into the response's own buffer, so this can fail with `ResponseError::HeaderBufferExceeded`.
+    pub fn with_cookie(&mut self, cookie: &SetCookie) -> Result<(), ResponseError> {
+        self.headers
+            .append_formatted("Set-Cookie", format_args!("{}", cookie))
+    }
+
+    /// The length of a body sent with a `Content-Length`, after any ranges are applied.
+    fn body_len(&self) -> usize {
+        match self.ranged() {
+            None => self.complete_body_len(),
+            Some(ranged) if ranged.is_multipart() => {
+                // Count the part headers, then add the data they frame
+                let mut counter = SizeCounter::new();
+                let mut output = PassOutput::new(&mut counter, 0);
+                let _ = self.execute_write_pass(
+                    &WritePass::WriteByteRanges {
+                        ranged,
+                        data: false,
+                    },
+                    &mut output,
+                );
+                let data: usize = ranged
+                    .ranges()
+                    .iter()
+                    .map(|(first, last)| last - first + 1)
+                    .sum();
+                counter.len() + data
             }
+            Some(ranged) => ranged
+                .ranges()
+                .first()
+                .map_or(0, |(first, last)| last - first + 1),
         }
+    }
 
-        Ok(())
+    /// The length of the whole body.
+    fn complete_body_len(&self) -> usize {
+        match self.body {
+            Body::Bytes(body) => body.len(),
+            Body::Sized(_, len) => len,
+            Body::Chunked(_) | Body::UntilClose(_) => 0,
+        }
     }
 
     fn version() -> &'static str {
@@ -75,18 +313,29 @@ impl<'a> Response<'a> {
     }
 
     /// Returns the size of the buffer that should be used for writing the response.
+    /// A body with a `Content-Length` is counted by that length without being produced, so a file isn't read just to size it.
     pub fn request_size(&self) -> usize {
-        let mut byte_index = 0;
+        let mut counter = SizeCounter::new();
+        let mut output = PassOutput::new(&mut counter, 0);
 
-        // Execute the write passes, calculating the total size
+        // Execute the write passes, calculating the total size. Counting never fails.
         for pass in Self::write_passes() {
-            let size = self.execute_write_pass(pass, 0, &mut []);
-            byte_index += size;
+            match (pass, &self.body) {
+                (WritePass::WriteBody, Body::Bytes(_) | Body::Sized(..)) => {}
+                _ => {
+                    let _ = self.execute_write_pass(pass, &mut output);
+                }
+            }
         }
 
-        byte_index
+        let body_len = match self.body {
+            Body::Bytes(_) | Body::Sized(..) if self.writes_body() => self.body_len(),
+            _ => 0,
+        };
+        output.position() + body_len
     }
 
+    /// `status-line CRLF *( field-line CRLF ) CRLF [ message-body ]`, as in RFC 9112 section 2.1. Each header writes its own CRLF.
     fn write_passes<'b>() -> &'b [WritePass<'b>] {
         &[
             WritePass::WriteVersion,
@@ -95,130 +344,375 @@ impl<'a> Response<'a> {
             WritePass::WriteNewline,
             WritePass::WriteHeaders,
             WritePass::WriteNewline,
-            WritePass::WriteNewline,
             WritePass::WriteBody,
         ]
     }
 
-    /// Executes a write pass. In the event that an empty buffer is passed, will not write to the buffer but will instead calculate the size that would be returned.
-    fn execute_write_pass(&self, pass: &WritePass, start_index: usize, buffer: &mut [u8]) -> usize {
-        let skip_buffering = buffer.len() == 0;
-
-        // TODO: stack overflow stuff? Not worrying for now...
-
-        let mut byte_index = start_index;
-        let mut data_to_copy: &[u8] = &[];
-
-        match pass {
-            WritePass::WriteVersion => {
-                data_to_copy = &Self::version().as_bytes();
-            }
+    /// Executes a write pass, sending its bytes to the output.
+    fn execute_write_pass<W: ResponseWriter>(
+        &self,
+        pass: &WritePass,
+        output: &mut PassOutput<W>,
+    ) -> Result<(), StreamError> {
+        let data_to_copy: &[u8] = match pass {
+            WritePass::WriteVersion => Self::version().as_bytes(),
             WritePass::WriteStatus => {
-                data_to_copy = &self.status.to_status().as_bytes();
+                let status = self.status().to_status();
+                self.execute_write_pass(&WritePass::WriteBytes(status.code()), output)?;
+                self.execute_write_pass(&WritePass::WriteSpace, output)?;
+                status.reason().as_bytes()
             }
             WritePass::WriteHeaders => {
-                // Write content length
-                {
-                    use numtoa::NumToA;
-                    let mut test_buff: [u8; 20] = [0; 20];
-                    let len = self.body.len().numtoa_str(10, &mut test_buff);
+                match &self.body {
+                    _ if !self.allows_body() => {}
+                    Body::UntilClose(_) => {}
+                    Body::Bytes(_) | Body::Sized(..) => {
+                        // Write content length, unless it was set explicitly for the whole body
+                        if self.ranged().is_some() || !self.headers.contains("Content-Length") {
+                            use numtoa::NumToA;
+                            let mut test_buff: [u8; 20] = [0; 20];
+                            let len = self.body_len().numtoa_str(10, &mut test_buff);
 
-                    byte_index += self.execute_write_pass(
-                        &WritePass::WriteHeader {
-                            key: "Content-Length".as_bytes(),
-                            value: len.as_bytes(),
-                        },
-                        byte_index,
-                        buffer,
-                    );
+                            self.execute_write_pass(
+                                &WritePass::WriteHeader {
+                                    key: "Content-Length".as_bytes(),
+                                    value: len.as_bytes(),
+                                },
+                                output,
+                            )?;
+                        }
+                    }
+                    Body::Chunked(producer) => {
+                        self.execute_write_pass(
+                            &WritePass::WriteHeader {
+                                key: b"Transfer-Encoding",
+                                value: b"chunked",
+                            },
+                            output,
+                        )?;
+
+                        // Announce the trailers, unless they were announced explicitly
+                        let trailers = producer.trailers();
+                        if !trailers.is_empty() && !self.headers.contains("Trailer") {
+                            self.execute_write_pass(&WritePass::WriteBytes(b"Trailer:"), output)?;
+                            for (i, trailer) in trailers.iter().enumerate() {
+                                let separator: &[u8] = if i == 0 { b" " } else { b", " };
+                                self.execute_write_pass(&WritePass::WriteBytes(separator), output)?;
+                                self.execute_write_pass(
+                                    &WritePass::WriteBytes(trailer.key.as_bytes()),
+                                    output,
+                                )?;
+                            }
+                            self.execute_write_pass(&WritePass::WriteNewline, output)?;
+                        }
+                    }
+                }
+
+                // Say which part of the body is sent
+                let ranged = self.ranged();
+                match ranged {
+                    Some(ranged) if ranged.is_multipart() => {
+                        self.execute_write_pass(
+                            &WritePass::WriteBytes(
+                                b"Content-Type: multipart/byteranges; boundary=",
+                            ),
+                            output,
+                        )?;
+                        self.execute_write_pass(
+                            &WritePass::WriteBytes(ranged.boundary.as_bytes()),
+                            output,
+                        )?;
+                        self.execute_write_pass(&WritePass::WriteNewline, output)?;
+                    }
+                    Some(ranged) => {
+                        self.execute_write_pass(
+                            &WritePass::WriteContentRange {
+                                range: ranged.ranges().first().copied(),
+                                complete_len: ranged.complete_len,
+                            },
+                            output,
+                        )?;
+                    }
+                    None => {}
                 }
 
                 // Write other headers
-                for header in &self.headers {
-                    match header {
-                        Some(header) => {
-                            byte_index += self.execute_write_pass(
-                                &WritePass::WriteHeader {
-                                    key: &header.key.as_bytes(),
-                                    value: &header.value.as_bytes(),
-                                },
-                                byte_index,
-                                buffer,
-                            );
+                for (key, value) in self.headers.iter() {
+                    // Chunked framing replaces any length the caller set, and bodiless statuses have none
+                    if (self.is_chunked() || !self.allows_body())
+                        && (key.eq_ignore_ascii_case("Content-Length")
+                            || key.eq_ignore_ascii_case("Transfer-Encoding"))
+                    {
+                        continue;
+                    }
+
+                    // A body ended by the connection closing has no length, coding or trailers
+                    if matches!(self.body, Body::UntilClose(_))
+                        && (key.eq_ignore_ascii_case("Content-Length")
+                            || key.eq_ignore_ascii_case("Transfer-Encoding")
+                            || key.eq_ignore_ascii_case("Trailer"))
+                    {
+                        continue;
+                    }
+
+                    // A ranged body has its own length and range, and the type of multiple parts moves into each part
+                    if let Some(ranged) = ranged {
+                        if key.eq_ignore_ascii_case("Content-Length")
+                            || key.eq_ignore_ascii_case("Content-Range")
+                            || (ranged.is_multipart() && key.eq_ignore_ascii_case("Content-Type"))
+                        {
+                            continue;
                         }
-                        None => {}
                     }
+
+                    self.execute_write_pass(
+                        &WritePass::WriteHeader {
+                            key: key.as_bytes(),
+                            value: value.as_bytes(),
+                        },
+                        output,
+                    )?;
                 }
-            }
 
-            WritePass::WriteNewline => {
-                data_to_copy = &Self::newline().as_bytes();
-            }
-            WritePass::WriteBody => {
-                data_to_copy = &self.body;
+                &[]
             }
-            WritePass::WriteSpace => {
-                data_to_copy = &Self::space().as_bytes();
-            }
-            WritePass::WriteBytes(bytes) => {
-                data_to_copy = bytes;
-            }
-            WritePass::WriteHeader { key, value } => {
-                // Write key
-                byte_index +=
-                    self.execute_write_pass(&WritePass::WriteBytes(key), byte_index, buffer);
-
-                // Write separator
-                byte_index += self.execute_write_pass(
-                    &WritePass::WriteBytes(":".as_bytes()),
-                    byte_index,
-                    buffer,
-                );
+            WritePass::WriteNewline => Self::newline().as_bytes(),
+            WritePass::WriteBody => match (self.ranged(), &self.body) {
+                _ if !self.writes_body() => &[],
+                (Some(ranged), _) if ranged.is_multipart() => {
+                    self.execute_write_pass(
+                        &WritePass::WriteByteRanges { ranged, data: true },
+                        output,
+                    )?;
+                    &[]
+                }
+                (Some(ranged), _) => {
+                    if let Some(&(first, last)) = ranged.ranges().first() {
+                        self.execute_write_pass(
+                            &WritePass::WriteBodyRange { first, last },
+                            output,
+                        )?;
+                    }
+                    &[]
+                }
+                (None, Body::Bytes(body)) => body,
+                (None, &Body::Chunked(producer)) => {
+                    self.execute_write_pass(&WritePass::WriteChunks(producer), output)?;
+                    &[]
+                }
+                (None, Body::UntilClose(producer)) => {
+                    let mut buffer = [0; CHUNK_BUFFER_BYTES];
+                    let mut index = 0;
+                    while let Some(chunk) = producer.chunk(index, &mut buffer) {
+                        index += 1;
+                        self.execute_write_pass(&WritePass::WriteBytes(chunk), output)?;
+                    }
+                    &[]
+                }
+                (None, &Body::Sized(_, len)) => {
+                    if len > 0 {
+                        self.execute_write_pass(
+                            &WritePass::WriteBodyRange {
+                                first: 0,
+                                last: len - 1,
+                            },
+                            output,
+                        )?;
+                    }
+                    &[]
+                }
+            },
+            WritePass::WriteBodyRange { first, last } => match &self.body {
+                Body::Bytes(body) => &body[*first..=*last],
+                Body::Sized(producer, _) => {
+                    let producer = producer.get();
+                    let mut buffer = [0; CHUNK_BUFFER_BYTES];
+                    let end = last + 1;
+                    // Start at the fragment holding the first byte when the fragments are evenly sized
+                    let mut index = match producer.fragment_len() {
+                        Some(fragment_len) if fragment_len > 0 => first / fragment_len,
+                        _ => 0,
+                    };
+                    let mut offset = producer.fragment_len().map_or(0, |len| index * len);
 
-                // Write space
-                byte_index += self.execute_write_pass(&WritePass::WriteSpace, byte_index, buffer);
+                    while offset < end {
+                        let chunk = match producer.chunk(index, &mut buffer) {
+                            Some(chunk) => chunk,
+                            None => break,
+                        };
+                        index += 1;
+
+                        let chunk_start = offset;
+                        offset += chunk.len();
+                        if offset <= *first {
+                            continue;
+                        }
 
-                // Write value
-                byte_index +=
-                    self.execute_write_pass(&WritePass::WriteBytes(value), byte_index, buffer);
+                        let from = first.saturating_sub(chunk_start);
+                        let to = end.min(offset) - chunk_start;
+                        self.execute_write_pass(&WritePass::WriteBytes(&chunk[from..to]), output)?;
+                    }
+
+                    &[]
+                }
+                Body::Chunked(_) | Body::UntilClose(_) => &[],
+            },
+            WritePass::WriteByteRanges { ranged, data } => {
+                // Each part, as in RFC 9110 section 14.6, then the closing delimiter
+                for &(first, last) in ranged.ranges() {
+                    self.execute_write_pass(&WritePass::WriteBytes(b"--"), output)?;
+                    self.execute_write_pass(
+                        &WritePass::WriteBytes(ranged.boundary.as_bytes()),
+                        output,
+                    )?;
+                    self.execute_write_pass(&WritePass::WriteNewline, output)?;
+                    if let Some(content_type) = self.headers.get("Content-Type") {
+                        self.execute_write_pass(
+                            &WritePass::WriteHeader {
+                                key: b"Content-Type",
+                                value: content_type.as_bytes(),
+                            },
+                            output,
+                        )?;
+                    }
+                    self.execute_write_pass(
+                        &WritePass::WriteContentRange {
+                            range: Some((first, last)),
+                            complete_len: ranged.complete_len,
+                        },
+                        output,
+                    )?;
+                    self.execute_write_pass(&WritePass::WriteNewline, output)?;
+                    if *data {
+                        self.execute_write_pass(
+                            &WritePass::WriteBodyRange { first, last },
+                            output,
+                        )?;
+                    }
+                    self.execute_write_pass(&WritePass::WriteNewline, output)?;
+                }
 
-                // Write newline
-                byte_index += self.execute_write_pass(&WritePass::WriteNewline, byte_index, buffer);
+                self.execute_write_pass(&WritePass::WriteBytes(b"--"), output)?;
+                self.execute_write_pass(
+                    &WritePass::WriteBytes(ranged.boundary.as_bytes()),
+                    output,
+                )?;
+                self.execute_write_pass(&WritePass::WriteBytes(b"--"), output)?;
+                Self::newline().as_bytes()
             }
-        }
+            WritePass::WriteContentRange {
+                range,
+                complete_len,
+            } => {
+                use numtoa::NumToA;
+                self.execute_write_pass(&WritePass::WriteBytes(b"Content-Range: bytes "), output)?;
+                match range {
+                    Some((first, last)) => {
+                        let mut buff: [u8; 20] = [0; 20];
+                        self.execute_write_pass(
+                            &WritePass::WriteBytes(first.numtoa(10, &mut buff)),
+                            output,
+                        )?;
+                        self.execute_write_pass(&WritePass::WriteBytes(b"-"), output)?;
+                        self.execute_write_pass(
+                            &WritePass::WriteBytes(last.numtoa(10, &mut buff)),
+                            output,
+                        )?;
+                    }
+                    // An unsatisfiable range only gives the length
+                    None => self.execute_write_pass(&WritePass::WriteBytes(b"*"), output)?,
+                }
+                self.execute_write_pass(&WritePass::WriteBytes(b"/"), output)?;
+                let mut buff: [u8; 20] = [0; 20];
+                self.execute_write_pass(
+                    &WritePass::WriteBytes(complete_len.numtoa(10, &mut buff)),
+                    output,
+                )?;
+                Self::newline().as_bytes()
+            }
+            WritePass::WriteChunks(producer) => {
+                use numtoa::NumToA;
+                let mut buffer = [0; CHUNK_BUFFER_BYTES];
+                let mut index = 0;
+
+                while let Some(chunk) = producer.chunk(index, &mut buffer) {
+                    index += 1;
+                    if chunk.is_empty() {
+                        continue;
+                    }
 
-        // Write the data to the buffer
-        if data_to_copy.len() > 0 {
-            for byte in data_to_copy {
-                if !skip_buffering {
-                    buffer[byte_index] = *byte;
+                    let mut size_buff: [u8; 20] = [0; 20];
+                    let size = chunk.len().numtoa(16, &mut size_buff);
+                    self.execute_write_pass(&WritePass::WriteBytes(size), output)?;
+                    self.execute_write_pass(&WritePass::WriteNewline, output)?;
+                    self.execute_write_pass(&WritePass::WriteBytes(chunk), output)?;
+                    self.execute_write_pass(&WritePass::WriteNewline, output)?;
                 }
-                byte_index += 1;
+
+                // The last chunk, then the trailer section
+                self.execute_write_pass(&WritePass::WriteBytes(b"0"), output)?;
+                self.execute_write_pass(&WritePass::WriteNewline, output)?;
+                for trailer in producer.trailers() {
+                    self.execute_write_pass(
+                        &WritePass::WriteHeader {
+                            key: trailer.key.as_bytes(),
+                            value: trailer.value.as_bytes(),
+                        },
+                        output,
+                    )?;
+                }
+
+                Self::newline().as_bytes()
             }
-        }
+            WritePass::WriteSpace => Self::space().as_bytes(),
+            WritePass::WriteBytes(bytes) => bytes,
+            WritePass::WriteHeader { key, value } => {
+                self.execute_write_pass(&WritePass::WriteBytes(key), output)?;
+                self.execute_write_pass(&WritePass::WriteBytes(b":"), output)?;
+                self.execute_write_pass(&WritePass::WriteSpace, output)?;
+                self.execute_write_pass(&WritePass::WriteBytes(value), output)?;
+                self.execute_write_pass(&WritePass::WriteNewline, output)?;
+                &[]
+            }
+        };
 
-        // Return the size of the written data
-        return byte_index - start_index;
+        output.write(data_to_copy)
     }
 
     /// Writes the response and body to the buffer, returning the number of written bytes.
     pub fn write_to_buffer(&self, buffer: &mut [u8]) -> Result<usize, StreamError> {
-        if buffer.len() < self.request_size() {
+        let size = self.request_size();
+        if buffer.len() < size {
             return Err(StreamError::BufferOverflow {
                 max_bytes: buffer.len(),
-                actual_size: self.request_size(),
+                actual_size: size,
             });
         }
 
-        let mut byte_index = 0;
+        self.write_to(&mut SliceWriter::new(buffer))
+    }
+
+    /// Streams the response into the writer piece by piece, returning the number of written bytes.
+    /// If the writer applies backpressure, `StreamError::WouldBlock` says how many bytes were taken; pass that to `resume_write_to` once the writer can accept more.
+    pub fn write_to<W: ResponseWriter>(&self, writer: W) -> Result<usize, StreamError> {
+        self.resume_write_to(writer, 0)
+    }
+
+    /// Like `write_to`, but skips the first `offset` bytes, which were already written by an earlier call.
+    /// Returns the total size of the response, including the skipped bytes.
+    pub fn resume_write_to<W: ResponseWriter>(
+        &self,
+        writer: W,
+        offset: usize,
+    ) -> Result<usize, StreamError> {
+        let mut output = PassOutput::new(writer, offset);
 
-        // Execute the write passes, adding it to the buffer
+        // Execute the write passes, sending them to the writer
         for pass in Self::write_passes() {
-            let size = self.execute_write_pass(pass, 0, &mut buffer[byte_index..]);
-            byte_index += size;
+            self.execute_write_pass(pass, &mut output)?;
         }
 
-        Ok(byte_index)
+        output.finish()
     }
 }
 
@@ -226,14 +720,710 @@ enum WritePass<'a> {
     WriteVersion,
     WriteStatus,
     WriteHeaders,
-    WriteHeader { key: &'a [u8], value: &'a [u8] },
+    WriteHeader {
+        key: &'a [u8],
+        value: &'a [u8],
+    },
     WriteBytes(&'a [u8]),
     WriteBody,
+    WriteChunks(&'a dyn BodyProducer),
+    /// Bytes `first` through `last` of the body, inclusive.
+    WriteBodyRange {
+        first: usize,
+        last: usize,
+    },
+    /// The `multipart/byteranges` body, or just its framing when `data` is false.
+    WriteByteRanges {
+        ranged: Ranged<'a>,
+        data: bool,
+    },
+    /// A `Content-Range` header, for the range or for an unsatisfiable one.
+    WriteContentRange {
+        range: Option<(usize, usize)>,
+        complete_len: usize,
+    },
     WriteNewline,
     WriteSpace,
 }
 
 #[cfg(test)]
 mod tests {
+    extern crate std;
     use super::*;
+
+    fn write(response: &Response) -> ([u8; 512], usize) {
+        let mut buffer = [0; 512];
+        let len = response.write_to_buffer(&mut buffer).unwrap();
+        (buffer, len)
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        crate::parser::find(haystack, needle).is_some()
+    }
+
+    #[test]
+    fn with_header_borrows_value() {
+        let location = [b'/', b'u', b'/', b'1'];
+        let location = core::str::from_utf8(&location).unwrap();
+
+        let mut response = Response::new(Status::Found);
+        response
+            .with_header(Header {
+                key: "Location",
+                value: location,
+            })
+            .unwrap();
+
+        let (buffer, len) = write(&response);
+        assert!(contains(&buffer[..len], b"Location: /u/1\r\n"));
+    }
+
+    #[test]
+    fn with_formatted_header_writes_value() {
+        let mut response = Response::new(Status::Ok);
+        response
+            .with_formatted_header("ETag", format_args!("\"{:x}\"", 0xbeefu32))
+            .unwrap();
+        response
+            .with_formatted_header(
+                "Content-Type",
+                format_args!("{}", ContentType::Html.with_charset("utf-8")),
+            )
+            .unwrap();
+
+        let (buffer, len) = write(&response);
+        assert!(contains(&buffer[..len], b"ETag: \"beef\"\r\n"));
+        assert!(contains(
+            &buffer[..len],
+            b"Content-Type: text/html; charset=utf-8\r\n"
+        ));
+        assert_eq!(len, response.request_size());
+    }
+
+    #[test]
+    fn headers_are_written_in_insertion_order() {
+        let mut response = Response::new(Status::Ok);
+        response
+            .with_header(Header {
+                key: "A",
+                value: "1",
+            })
+            .unwrap();
+        response
+            .with_header(Header {
+                key: "B",
+                value: "2",
+            })
+            .unwrap();
+        response
+            .with_header(Header {
+                key: "C",
+                value: "3",
+            })
+            .unwrap();
+
+        let (buffer, len) = write(&response);
+        assert!(contains(&buffer[..len], b"A: 1\r\nB: 2\r\nC: 3\r\n"));
+    }
+
+    #[test]
+    fn set_header_replaces_existing() {
+        let mut response = Response::new(Status::Ok);
+        response
+            .append_header(Header {
+                key: "Content-Type",
+                value: "text/plain",
+            })
+            .unwrap();
+        response
+            .set_header(Header {
+                key: "content-type",
+                value: "application/json",
+            })
+            .unwrap();
+
+        assert_eq!(Some("application/json"), response.header("Content-Type"));
+
+        let (buffer, len) = write(&response);
+        assert!(!contains(&buffer[..len], b"text/plain"));
+    }
+
+    #[test]
+    fn remove_header_removes_from_output() {
+        let mut response = Response::new(Status::Ok);
+        response
+            .append_header(Header {
+                key: "X-A",
+                value: "1",
+            })
+            .unwrap();
+
+        assert!(response.remove_header("x-a"));
+        assert_eq!(None, response.header("X-A"));
+
+        let (buffer, len) = write(&response);
+        assert!(!contains(&buffer[..len], b"X-A"));
+    }
+
+    #[test]
+    fn content_length_is_written_automatically() {
+        let mut response = Response::new(Status::Ok);
+        response.with_body(b"hello").unwrap();
+
+        let (buffer, len) = write(&response);
+        assert!(contains(&buffer[..len], b"Content-Length: 5\r\n"));
+    }
+
+    #[test]
+    fn explicit_content_length_overrides_automatic() {
+        let mut response = Response::new(Status::Ok);
+        response.with_body(b"hello").unwrap();
+        response
+            .set_header(Header {
+                key: "content-length",
+                value: "5",
+            })
+            .unwrap();
+
+        let (buffer, len) = write(&response);
+        assert!(contains(&buffer[..len], b"content-length: 5\r\n"));
+        assert!(!contains(&buffer[..len], b"Content-Length"));
+        assert_eq!(len, response.request_size());
+    }
+
+    /// Stores everything written to it, but accepts at most `per_write` bytes per call and blocks once `capacity` is reached.
+    struct Sink {
+        data: [u8; 512],
+        len: usize,
+        per_write: usize,
+        capacity: usize,
+    }
+
+    impl ResponseWriter for Sink {
+        fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+            let len = bytes
+                .len()
+                .min(self.per_write)
+                .min(self.capacity - self.len);
+            self.data[self.len..self.len + len].copy_from_slice(&bytes[..len]);
+            self.len += len;
+            Ok(len)
+        }
+    }
+
+    fn example_response<'a>() -> Response<'a> {
+        let mut response = Response::new(Status::Ok);
+        response.with_body(b"<p>streamed</p>").unwrap();
+        response
+            .with_header(Header {
+                key: "Content-Type",
+                value: "text/html",
+            })
+            .unwrap();
+        response
+    }
+
+    #[test]
+    fn write_to_matches_write_to_buffer() {
+        let response = example_response();
+        let (expected, expected_len) = write(&response);
+
+        let mut sink = Sink {
+            data: [0; 512],
+            len: 0,
+            per_write: 512,
+            capacity: 512,
+        };
+        let len = response.write_to(&mut sink).unwrap();
+
+        assert_eq!(expected_len, len);
+        assert_eq!(&expected[..expected_len], &sink.data[..sink.len]);
+    }
+
+    #[test]
+    fn write_to_handles_partial_writes() {
+        let response = example_response();
+        let (expected, expected_len) = write(&response);
+
+        let mut sink = Sink {
+            data: [0; 512],
+            len: 0,
+            per_write: 3,
+            capacity: 512,
+        };
+        response.write_to(&mut sink).unwrap();
+
+        assert_eq!(&expected[..expected_len], &sink.data[..sink.len]);
+    }
+
+    #[test]
+    fn write_to_reports_backpressure_and_resumes() {
+        let response = example_response();
+        let (expected, expected_len) = write(&response);
+
+        let mut sink = Sink {
+            data: [0; 512],
+            len: 0,
+            per_write: 512,
+            capacity: 10,
+        };
+
+        let offset = match response.write_to(&mut sink) {
+            Err(StreamError::WouldBlock { bytes_written }) => bytes_written,
+            other => panic!("expected backpressure, got {:?}", other),
+        };
+        assert_eq!(10, offset);
+
+        // The sink drains, then writing picks up where it stopped
+        sink.capacity = 512;
+        let len = response.resume_write_to(&mut sink, offset).unwrap();
+
+        assert_eq!(expected_len, len);
+        assert_eq!(&expected[..expected_len], &sink.data[..sink.len]);
+    }
+
+    #[test]
+    fn write_to_through_buffered_writer() {
+        let response = example_response();
+        let (expected, expected_len) = write(&response);
+
+        let sink = Sink {
+            data: [0; 512],
+            len: 0,
+            per_write: 7,
+            capacity: 512,
+        };
+        let mut scratch = [0; 16];
+        let mut writer = BufferedWriter::new(sink, &mut scratch);
+        response.write_to(&mut writer).unwrap();
+
+        let sink = writer.into_inner();
+        assert_eq!(&expected[..expected_len], &sink.data[..sink.len]);
+    }
+
+    #[test]
+    fn write_to_buffer_too_small_returns_err() {
+        let response = example_response();
+        let mut buffer = [0; 8];
+
+        assert_eq!(
+            Err(StreamError::BufferOverflow {
+                max_bytes: 8,
+                actual_size: response.request_size()
+            }),
+            response.write_to_buffer(&mut buffer)
+        );
+    }
+
+    /// Renders numbered list items into the chunk buffer, as a template would.
+    struct Items {
+        count: usize,
+    }
+
+    impl BodyProducer for Items {
+        fn chunk<'s>(&'s self, index: usize, buffer: &'s mut [u8]) -> Option<&'s [u8]> {
+            if index >= self.count {
+                return None;
+            }
+
+            buffer[..5].copy_from_slice(b"<li>0");
+            buffer[4] += index as u8;
+            Some(&buffer[..5])
+        }
+
+        fn trailers(&self) -> &[Header<'_>] {
+            &[Header {
+                key: "X-Items",
+                value: "3",
+            }]
+        }
+    }
+
+    #[test]
+    fn chunked_body_writes_chunks_and_trailers() {
+        let items = Items { count: 3 };
+        let mut response = Response::new(Status::Ok);
+        response.with_chunked_body(&items);
+
+        let (buffer, len) = write(&response);
+        let written = &buffer[..len];
+        assert!(contains(written, b"Transfer-Encoding: chunked\r\n"));
+        assert!(contains(written, b"Trailer: X-Items\r\n"));
+        assert!(!contains(written, b"Content-Length"));
+        assert!(contains(
+            written,
+            b"5\r\n<li>0\r\n5\r\n<li>1\r\n5\r\n<li>2\r\n0\r\nX-Items: 3\r\n\r\n"
+        ));
+        assert!(written.ends_with(b"0\r\nX-Items: 3\r\n\r\n"));
+        assert_eq!(len, response.request_size());
+    }
+
+    #[test]
+    fn chunked_body_skips_empty_fragments_and_writes_hex_sizes() {
+        let large = [b'a'; 26];
+        let fragments = [&b""[..], &large[..], b""];
+        let fragments = Fragments::new(&fragments);
+
+        let mut response = Response::new(Status::Ok);
+        response.with_chunked_body(&fragments);
+
+        let (buffer, len) = write(&response);
+        let written = &buffer[..len];
+        assert!(contains(written, b"\r\n1A\r\naaaa"));
+        assert!(written.ends_with(b"aaaa\r\n0\r\n\r\n"));
+        assert!(!contains(written, b"Trailer"));
+    }
+
+    #[test]
+    fn chunked_body_leaves_out_content_length() {
+        let fragments = [&b"hello"[..]];
+        let fragments = Fragments::new(&fragments);
+
+        let mut response = Response::new(Status::Ok);
+        response
+            .set_header(Header {
+                key: "Content-Length",
+                value: "5",
+            })
+            .unwrap();
+        response.with_chunked_body(&fragments);
+        assert!(response.is_chunked());
+
+        let (buffer, len) = write(&response);
+        assert!(!contains(&buffer[..len], b"Content-Length"));
+    }
+
+    #[test]
+    fn close_delimited_body_is_written_raw() {
+        let items = Items { count: 3 };
+        let mut response = Response::new(Status::Ok);
+        response
+            .set_header(Header {
+                key: "Trailer",
+                value: "X-Items",
+            })
+            .unwrap();
+        response.with_chunked_body(&items);
+        response.delimit_by_close();
+        assert!(!response.is_chunked());
+
+        let (buffer, len) = write(&response);
+        assert_eq!(
+            &b"HTTP/1.1 200 OK\r\n\r\n<li>0<li>1<li>2"[..],
+            &buffer[..len]
+        );
+        assert_eq!(len, response.request_size());
+    }
+
+    #[test]
+    fn chunked_body_resumes_after_backpressure() {
+        let items = Items { count: 3 };
+        let mut response = Response::new(Status::Ok);
+        response.with_chunked_body(&items);
+        let (expected, expected_len) = write(&response);
+
+        let mut sink = Sink {
+            data: [0; 512],
+            len: 0,
+            per_write: 512,
+            capacity: expected_len - 12,
+        };
+        let offset = match response.write_to(&mut sink) {
+            Err(StreamError::WouldBlock { bytes_written }) => bytes_written,
+            other => panic!("expected backpressure, got {:?}", other),
+        };
+
+        sink.capacity = 512;
+        response.resume_write_to(&mut sink, offset).unwrap();
+        assert_eq!(&expected[..expected_len], &sink.data[..sink.len]);
+    }
+
+    #[test]
+    fn sized_body_writes_content_length_and_cuts_off_fragments() {
+        let fragments = [&b"hel"[..], b"", b"lo, world"];
+        let fragments = Fragments::new(&fragments);
+
+        let mut response = Response::new(Status::Ok);
+        response.with_sized_body(&fragments, 5);
+        assert!(!response.is_chunked());
+
+        let (buffer, len) = write(&response);
+        let written = &buffer[..len];
+        assert!(contains(written, b"Content-Length: 5\r\n"));
+        assert!(!contains(written, b"Transfer-Encoding"));
+        assert!(written.ends_with(b"\r\n\r\nhello"));
+        assert_eq!(len, response.request_size());
+    }
+
+    #[test]
+    fn request_size_does_not_produce_sized_body() {
+        /// Fails the test if any fragment is produced.
+        struct Unread;
+
+        impl BodyProducer for Unread {
+            fn chunk<'s>(&'s self, index: usize, _buffer: &'s mut [u8]) -> Option<&'s [u8]> {
+                panic!("produced fragment {}", index)
+            }
+        }
+
+        let mut response = Response::new(Status::Ok);
+        response.with_sized_body(&Unread, 1 << 20);
+        assert_eq!(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 1048576\r\n\r\n".len() + (1 << 20),
+            response.request_size()
+        );
+
+        // The framing of the parts is counted without producing them either.
+        response.with_ranges(&Ranges::parse("bytes=0-9,-10").unwrap(), "SEP");
+        assert!(response.request_size() > 20);
+    }
+
+    fn ranged(body: &[u8], range: &str) -> ([u8; 512], usize) {
+        let mut response = Response::new(Status::Ok);
+        response.with_body(body).unwrap();
+        response
+            .with_header(Header {
+                key: "Content-Type",
+                value: "text/plain",
+            })
+            .unwrap();
+        response.with_ranges(&Ranges::parse(range).unwrap(), "SEP");
+
+        let (buffer, len) = write(&response);
+        assert_eq!(len, response.request_size());
+        (buffer, len)
+    }
+
+    #[test]
+    fn single_range_writes_partial_content() {
+        let (buffer, len) = ranged(b"hello, world", "bytes=7-");
+        let written = &buffer[..len];
+
+        assert!(written.starts_with(b"HTTP/1.1 206 Partial Content\r\n"));
+        assert!(contains(written, b"Content-Length: 5\r\n"));
+        assert!(contains(written, b"Content-Range: bytes 7-11/12\r\n"));
+        assert!(contains(written, b"Content-Type: text/plain\r\n"));
+        assert!(written.ends_with(b"\r\n\r\nworld"));
+    }
+
+    #[test]
+    fn multiple_ranges_write_byteranges_parts() {
+        let (buffer, len) = ranged(b"hello, world", "bytes=0-4, -5, 20-");
+        let written = &buffer[..len];
+
+        let body = b"--SEP\r\n\
+            Content-Type: text/plain\r\nContent-Range: bytes 0-4/12\r\n\r\nhello\r\n\
+            --SEP\r\n\
+            Content-Type: text/plain\r\nContent-Range: bytes 7-11/12\r\n\r\nworld\r\n\
+            --SEP--\r\n";
+        assert!(written.starts_with(b"HTTP/1.1 206 Partial Content\r\n"));
+        assert!(contains(
+            written,
+            b"Content-Type: multipart/byteranges; boundary=SEP\r\n"
+        ));
+        assert!(!contains(written, b"\r\nContent-Type: text/plain\r\n\r\n"));
+        assert!(written.ends_with(body));
+
+        let length = std::format!("Content-Length: {}\r\n", body.len());
+        assert!(contains(written, length.as_bytes()));
+    }
+
+    #[test]
+    fn unsatisfiable_ranges_write_416() {
+        let (buffer, len) = ranged(b"hello", "bytes=5-, -0");
+        let written = &buffer[..len];
+
+        assert!(written.starts_with(b"HTTP/1.1 416 Range Not Satisfiable\r\n"));
+        assert!(contains(written, b"Content-Range: bytes */5\r\n"));
+        assert!(contains(written, b"Content-Length: 0\r\n"));
+        assert!(written.ends_with(b"\r\n\r\n"));
+    }
+
+    #[test]
+    fn ranges_ignored_unless_ok() {
+        let mut response = Response::new(Status::NotFound);
+        response.with_body(b"missing").unwrap();
+        response.with_ranges(&Ranges::parse("bytes=0-0").unwrap(), "SEP");
+
+        assert_eq!(Status::NotFound, response.status());
+        let (buffer, len) = write(&response);
+        assert!(buffer[..len].ends_with(b"missing"));
+    }
+
+    #[test]
+    fn ranges_follow_body_set_later() {
+        let mut response = Response::new(Status::Ok);
+        response.with_body(b"hello, world").unwrap();
+        response.with_ranges(&Ranges::parse("bytes=4-11").unwrap(), "SEP");
+        response.with_body(b"hi, you").unwrap();
+
+        let (buffer, len) = write(&response);
+        let written = &buffer[..len];
+        assert_eq!(Status::PartialContent, response.status());
+        assert!(contains(written, b"Content-Range: bytes 4-6/7\r\n"));
+        assert!(written.ends_with(b"\r\n\r\nyou"));
+        assert_eq!(len, response.request_size());
+
+        response.with_body(b"hi").unwrap();
+        let (buffer, len) = write(&response);
+        assert_eq!(Status::RangeNotSatisfiable, response.status());
+        assert!(contains(&buffer[..len], b"Content-Range: bytes */2\r\n"));
+        assert!(buffer[..len].ends_with(b"\r\n\r\n"));
+    }
+
+    #[test]
+    fn ranges_of_sized_body_skip_to_fragment() {
+        /// Evenly sized fragments that must not be produced before the range.
+        struct Digits;
+
+        impl BodyProducer for Digits {
+            fn chunk<'s>(&'s self, index: usize, _buffer: &'s mut [u8]) -> Option<&'s [u8]> {
+                assert!(index >= 2, "produced fragment {}", index);
+                b"0123456789".get(index..index + 1)
+            }
+
+            fn fragment_len(&self) -> Option<usize> {
+                Some(1)
+            }
+        }
+
+        let mut response = Response::new(Status::Ok);
+        response.with_sized_body(&Digits, 10);
+        response.with_ranges(&Ranges::parse("bytes=2-4,8-").unwrap(), "SEP");
+
+        let (buffer, len) = write(&response);
+        let written = &buffer[..len];
+        assert!(contains(written, b"bytes 2-4/10\r\n\r\n234\r\n"));
+        assert!(contains(written, b"bytes 8-9/10\r\n\r\n89\r\n"));
+        assert_eq!(len, response.request_size());
+    }
+
+    #[test]
+    fn head_range_response_ignores_ranges() {
+        let mut response = Response::new(Status::Ok);
+        response.with_body(b"hello").unwrap();
+        response.with_request_method(Method::Head);
+        response.with_ranges(&Ranges::parse("bytes=1-2").unwrap(), "SEP");
+
+        let (buffer, len) = write(&response);
+        assert_eq!(Status::Ok, response.status());
+        assert!(buffer[..len].starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(!contains(&buffer[..len], b"Content-Range"));
+        assert!(contains(&buffer[..len], b"Content-Length: 5\r\n"));
+        assert!(buffer[..len].ends_with(b"\r\n\r\n"));
+    }
+
+    #[test]
+    fn head_response_keeps_content_length_without_body() {
+        let mut response = Response::new(Status::Ok);
+        response.with_body(b"hello").unwrap();
+        response.with_request_method(Method::Head);
+
+        let (buffer, len) = write(&response);
+        assert!(contains(&buffer[..len], b"Content-Length: 5\r\n"));
+        assert!(!contains(&buffer[..len], b"hello"));
+        assert_eq!(len, response.request_size());
+    }
+
+    #[test]
+    fn head_chunked_response_writes_no_chunks() {
+        let fragments = [&b"hello"[..]];
+        let fragments = Fragments::new(&fragments);
+
+        let mut response = Response::new(Status::Ok);
+        response.with_chunked_body(&fragments);
+        response.with_request_method(Method::Head);
+
+        let (buffer, len) = write(&response);
+        assert!(contains(&buffer[..len], b"Transfer-Encoding: chunked\r\n"));
+        assert!(!contains(&buffer[..len], b"hello"));
+        assert!(!contains(&buffer[..len], b"0\r\n\r\n"));
+    }
+
+    #[test]
+    fn bodiless_statuses_leave_out_body_and_content_length() {
+        for status in [
+            Status::Continue,
+            Status::SwitchingProtocols,
+            Status::NoContent,
+            Status::NotModified,
+        ]
+        .iter()
+        {
+            let mut response = Response::new(*status);
+            response.with_body(b"hello").unwrap();
+            response
+                .with_header(Header {
+                    key: "Content-Length",
+                    value: "5",
+                })
+                .unwrap();
+            response
+                .with_header(Header {
+                    key: "ETag",
+                    value: "\"1\"",
+                })
+                .unwrap();
+            assert!(!response.allows_body());
+
+            let (buffer, len) = write(&response);
+            let written = &buffer[..len];
+            assert!(!contains(written, b"Content-Length"));
+            assert!(!contains(written, b"hello"));
+            assert!(contains(written, b"ETag: \"1\"\r\n"));
+        }
+    }
+
+    #[test]
+    fn not_modified_leaves_out_chunked_framing() {
+        let fragments = [&b"hello"[..]];
+        let fragments = Fragments::new(&fragments);
+
+        let mut response = Response::new(Status::NotModified);
+        response.with_chunked_body(&fragments);
+
+        let (buffer, len) = write(&response);
+        assert!(!contains(&buffer[..len], b"Transfer-Encoding"));
+        assert!(!contains(&buffer[..len], b"hello"));
+    }
+
+    #[test]
+    fn with_cookie_writes_one_header_per_cookie() {
+        let mut response = Response::new(Status::Ok);
+        response
+            .with_cookie(&SetCookie::new("session", "abc").with_http_only())
+            .unwrap();
+        response
+            .with_cookie(&SetCookie::new("theme", "dark").with_same_site(SameSite::Strict))
+            .unwrap();
+
+        let (buffer, len) = write(&response);
+        assert!(contains(
+            &buffer[..len],
+            b"Set-Cookie: session=abc; HttpOnly\r\nSet-Cookie: theme=dark; SameSite=Strict\r\n"
+        ));
+    }
+
+    #[test]
+    fn with_formatted_header_overflow_returns_err() {
+        let mut response = Response::new(Status::Ok);
+        let long = [b'a'; HEADER_BUFFER_BYTES - 4];
+        let long = core::str::from_utf8(&long).unwrap();
+        response
+            .with_formatted_header("X-Long", format_args!("{}", long))
+            .unwrap();
+
+        let result = response.with_formatted_header("X-Overflow", format_args!("{}", "12345"));
+        assert!(matches!(
+            result,
+            Err(ResponseError::HeaderBufferExceeded {
+                max_bytes: HEADER_BUFFER_BYTES
+            })
+        ));
+
+        // The failed header must not be written
+        let mut buffer = [0; 2048];
+        let len = response.write_to_buffer(&mut buffer).unwrap();
+        assert!(!contains(&buffer[..len], b"X-Overflow"));
+    }
 }
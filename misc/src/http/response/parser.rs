@@ -0,0 +1,481 @@
+use super::error::Error;
+use super::{ContentType, MediaType, Status};
+use crate::http::request::Error as RequestError;
+use crate::http::request::{
+    content_length, decode_chunked, ends_with_chunked, parse_headers, parse_version, Headers,
+    HttpVersion, Method,
+};
+use crate::parser::{find, RawIter};
+
+const NEWLINE: &[u8] = b"\r\n";
+
+/// A response read from the wire. Everything borrows from the raw response.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ParsedResponse<'a> {
+    version: HttpVersion,
+    status: Status,
+    reason: &'a str,
+    headers: Headers<'a>,
+    body: &'a [u8],
+}
+
+impl<'a> ParsedResponse<'a> {
+    pub fn version(&self) -> HttpVersion {
+        self.version
+    }
+
+    /// The status for the code. Codes that aren't registered come back as `Status::Custom` with an empty reason; see `reason` for the phrase that was sent.
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// The reason phrase as sent, which may differ from the canonical one or be empty.
+    pub fn reason(&self) -> &'a str {
+        self.reason
+    }
+
+    /// Returns the value of the first header with the given name, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&'a str> {
+        self.headers.get(name)
+    }
+
+    pub fn headers(&self) -> &Headers<'a> {
+        &self.headers
+    }
+
+    /// The type from the `Content-Type` header, if it is one that is known.
+    pub fn content_type(&self) -> Option<ContentType> {
+        self.header("Content-Type").and_then(ContentType::parse)
+    }
+
+    /// The `Content-Type` header along with its charset and boundary, if the type is one that is known.
+    pub fn media_type(&self) -> Option<MediaType<'a>> {
+        self.header("Content-Type").and_then(MediaType::parse)
+    }
+
+    /// The body, decoded if it was chunked.
+    pub fn body(&self) -> &'a [u8] {
+        self.body
+    }
+}
+
+/// Parses a response to a `GET` or similar request. Chunked bodies require a buffer to decode into; use `parse_with_body_buffer` for those.
+pub fn parse(raw_response: &[u8]) -> Result<ParsedResponse<'_>, Error> {
+    parse_with_body_buffer(raw_response, &mut [])
+}
+
+/// Parses a response, decoding a chunked body into `body_buffer`. Bodies sized by `Content-Length` borrow from `raw_response` and leave the buffer untouched.
+pub fn parse_with_body_buffer<'a>(
+    raw_response: &'a [u8],
+    body_buffer: &'a mut [u8],
+) -> Result<ParsedResponse<'a>, Error> {
+    parse_response_to(Method::Get, raw_response, body_buffer)
+}
+
+/// Parses the response to a request made with `method`. A response to `HEAD` has no body, whatever its headers say.
+/// A response without `Content-Length`, or whose final transfer coding isn't `chunked`, is delimited by the connection closing,
+/// so its body is the rest of `raw_response`. There is no limit on the body beyond the sizes of `raw_response` and `body_buffer`.
+pub fn parse_response_to<'a>(
+    method: Method,
+    raw_response: &'a [u8],
+    body_buffer: &'a mut [u8],
+) -> Result<ParsedResponse<'a>, Error> {
+    let mut raw_iter = RawIter::new(raw_response);
+    let (version, status, reason) = parse_status_line(&mut raw_iter)?;
+    let headers = parse_headers(&mut raw_iter)?;
+
+    let body = match framing(method, status, &headers)? {
+        Framing::Empty => &[],
+        Framing::Fixed(len) => {
+            let data = raw_iter.data();
+            if data.len() < len {
+                return Err(Error::TruncatedBody);
+            }
+
+            raw_iter.advance(len);
+            &data[..len]
+        }
+        Framing::Chunked => {
+            decode_chunked(&mut raw_iter, body_buffer, usize::MAX).map_err(body_error)?
+        }
+        Framing::UntilClose => {
+            let rest = raw_iter.data();
+            raw_iter.advance(rest.len());
+            rest
+        }
+    };
+
+    Ok(ParsedResponse {
+        version,
+        status,
+        reason,
+        headers,
+        body,
+    })
+}
+
+/// How the body of a response is delimited, as in RFC 9112 section 6.3.
+#[derive(PartialEq, Copy, Clone, Debug)]
+enum Framing {
+    Empty,
+    /// Exactly this many bytes, as given by `Content-Length`.
+    Fixed(usize),
+    Chunked,
+    /// Everything up to the connection closing.
+    UntilClose,
+}
+
+fn framing(method: Method, status: Status, headers: &Headers) -> Result<Framing, Error> {
+    // A successful `CONNECT` turns the connection into a tunnel, so whatever follows isn't a body.
+    if method == Method::Head
+        || (method == Method::Connect && status.is_success())
+        || !status.allows_body()
+    {
+        return Ok(Framing::Empty);
+    }
+
+    // Unlike in a request, `Transfer-Encoding` overrides `Content-Length` in a response.
+    if let Some(encoding) = headers.get("Transfer-Encoding") {
+        return Ok(if ends_with_chunked(encoding) {
+            Framing::Chunked
+        } else {
+            Framing::UntilClose
+        });
+    }
+
+    let content_length = content_length(headers, usize::MAX).map_err(body_error)?;
+    Ok(content_length.map_or(Framing::UntilClose, Framing::Fixed))
+}
+
+/// Moves the body errors shared with requests onto the response's own variants.
+fn body_error(error: RequestError) -> Error {
+    match error {
+        RequestError::InvalidContentLength => Error::InvalidContentLength,
+        RequestError::ConflictingBodyLength => Error::ConflictingBodyLength,
+        RequestError::MalformedChunk => Error::MalformedChunk,
+        RequestError::TruncatedBody => Error::TruncatedBody,
+        RequestError::BodyBufferTooSmall { max_bytes, bytes } => {
+            Error::BodyBufferTooSmall { max_bytes, bytes }
+        }
+        // Without a limit, only chunk sizes too large for a usize go over it.
+        RequestError::BodySizeExceeded { .. } => Error::MalformedChunk,
+        error => Error::Message(error),
+    }
+}
+
+/// Parses `HTTP-version SP status-code SP [reason-phrase] CRLF`, advancing past it.
+fn parse_status_line<'a>(
+    response: &mut RawIter<'a>,
+) -> Result<(HttpVersion, Status, &'a str), Error> {
+    let version = parse_version(response)?;
+    expect(response, b" ", Error::ExpectedSpace)?;
+
+    let status = match response.data() {
+        [a, b, c, ..] if [a, b, c].iter().all(|d| d.is_ascii_digit()) => {
+            let code = [a, b, c]
+                .iter()
+                .fold(0, |code, d| code * 10 + u16::from(**d - b'0'));
+            Status::from_u16(code).ok_or(Error::InvalidStatusCode)?
+        }
+        _ => return Err(Error::InvalidStatusCode),
+    };
+    response.advance(3);
+    expect(response, b" ", Error::ExpectedSpace)?;
+
+    let data = response.data();
+    let end = find(data, NEWLINE).ok_or(Error::ExpectedNewline)?;
+    let reason = &data[..end];
+    if !reason
+        .iter()
+        .all(|b| *b == b'\t' || *b == b' ' || b.is_ascii_graphic())
+    {
+        return Err(Error::InvalidReasonPhrase);
+    }
+    response.advance(end + NEWLINE.len());
+
+    // Only ASCII was allowed through.
+    Ok((version, status, core::str::from_utf8(reason).unwrap()))
+}
+
+fn expect(response: &mut RawIter, expected: &[u8], error: Error) -> Result<(), Error> {
+    if !response.data().starts_with(expected) {
+        return Err(error);
+    }
+
+    response.advance(expected.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::response::{Fragments, Header, Response};
+
+    /// Serializes the response and parses it back.
+    fn round_trip<'b>(response: &Response, buffer: &'b mut [u8]) -> ParsedResponse<'b> {
+        let len = response.write_to_buffer(buffer).unwrap();
+        assert_eq!(len, response.request_size());
+        parse(&buffer[..len]).unwrap()
+    }
+
+    #[test]
+    fn round_trip_empty_response() {
+        let response = Response::new(Status::NotFound);
+        let mut buffer = [0; 256];
+        let len = response.write_to_buffer(&mut buffer).unwrap();
+        assert_eq!(
+            &b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n"[..],
+            &buffer[..len]
+        );
+
+        let parsed = parse(&buffer[..len]).unwrap();
+        assert_eq!(HttpVersion::Http11, parsed.version());
+        assert_eq!(Status::NotFound, parsed.status());
+        assert_eq!("Not Found", parsed.reason());
+        assert_eq!(Some("0"), parsed.header("Content-Length"));
+        assert!(parsed.body().is_empty());
+    }
+
+    #[test]
+    fn round_trip_body_starts_after_blank_line() {
+        let mut response = Response::new(Status::Ok);
+        response.with_body(b"\r\nhello\r\n").unwrap();
+        response
+            .with_header(Header {
+                key: "Content-Type",
+                value: ContentType::PlainText.to_header_value(),
+            })
+            .unwrap();
+
+        let mut buffer = [0; 256];
+        let len = response.write_to_buffer(&mut buffer).unwrap();
+        assert!(buffer[..len].ends_with(b"Content-Type: text/plain\r\n\r\n\r\nhello\r\n"));
+
+        let parsed = parse(&buffer[..len]).unwrap();
+        assert_eq!(b"\r\nhello\r\n", parsed.body());
+        assert_eq!(Some("text/plain"), parsed.header("content-type"));
+    }
+
+    #[test]
+    fn round_trip_keeps_headers_in_order() {
+        let mut response = Response::new(Status::Created);
+        response
+            .with_header(Header {
+                key: "Location",
+                value: "/users/1",
+            })
+            .unwrap();
+        response
+            .with_formatted_header("ETag", format_args!("\"{}\"", 7))
+            .unwrap();
+
+        let mut buffer = [0; 256];
+        let parsed = round_trip(&response, &mut buffer);
+        let mut headers = parsed.headers().iter();
+        assert_eq!("Content-Length", headers.next().unwrap().name());
+        assert_eq!("Location", headers.next().unwrap().name());
+        let etag = headers.next().unwrap();
+        assert_eq!(("ETag", "\"7\""), (etag.name(), etag.value()));
+        assert!(headers.next().is_none());
+    }
+
+    #[test]
+    fn round_trip_custom_status() {
+        let mut response = Response::new(Status::custom(299, "Fine I Guess").unwrap());
+        response.with_body(b"{}").unwrap();
+
+        let mut buffer = [0; 256];
+        let parsed = round_trip(&response, &mut buffer);
+        assert_eq!(299, parsed.status().code());
+        assert_eq!("Fine I Guess", parsed.reason());
+        assert_eq!(b"{}", parsed.body());
+    }
+
+    #[test]
+    fn round_trip_bodiless_status() {
+        let mut response = Response::new(Status::NoContent);
+        response.with_body(b"ignored").unwrap();
+
+        let mut buffer = [0; 256];
+        let len = response.write_to_buffer(&mut buffer).unwrap();
+        assert_eq!(&b"HTTP/1.1 204 No Content\r\n\r\n"[..], &buffer[..len]);
+        assert!(parse(&buffer[..len]).unwrap().body().is_empty());
+    }
+
+    #[test]
+    fn round_trip_chunked_body() {
+        let trailers = [Header {
+            key: "X-Checksum",
+            value: "abc",
+        }];
+        let fragments = [&b"<ul>"[..], b"<li>one</li>", b"</ul>"];
+        let fragments = Fragments::new(&fragments).with_trailers(&trailers);
+
+        let mut response = Response::new(Status::Ok);
+        response.with_chunked_body(&fragments);
+        response
+            .with_formatted_header(
+                "Content-Type",
+                format_args!("{}", ContentType::Html.with_charset("utf-8")),
+            )
+            .unwrap();
+
+        let mut buffer = [0; 256];
+        let len = response.write_to_buffer(&mut buffer).unwrap();
+        let mut body_buffer = [0; 64];
+        let parsed = parse_with_body_buffer(&buffer[..len], &mut body_buffer).unwrap();
+
+        assert_eq!(b"<ul><li>one</li></ul>", parsed.body());
+        assert_eq!(Some("chunked"), parsed.header("Transfer-Encoding"));
+        assert_eq!(Some(ContentType::Html), parsed.content_type());
+        assert_eq!(Some("utf-8"), parsed.media_type().unwrap().charset());
+    }
+
+    #[test]
+    fn parse_chunked_without_buffer_returns_err() {
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\n\r\n";
+        assert_eq!(
+            Err(Error::BodyBufferTooSmall {
+                max_bytes: 0,
+                bytes: 3
+            }),
+            parse(raw)
+        );
+    }
+
+    #[test]
+    fn parse_without_length_reads_to_end() {
+        let parsed =
+            parse(b"HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n{\"a\":1}").unwrap();
+        assert_eq!(b"{\"a\":1}", parsed.body());
+        assert_eq!(Some(ContentType::Json), parsed.content_type());
+    }
+
+    #[test]
+    fn parse_head_response_has_no_body() {
+        let mut response = Response::new(Status::Ok);
+        response.with_body(b"hello").unwrap();
+        response.with_request_method(Method::Head);
+
+        let mut buffer = [0; 256];
+        let len = response.write_to_buffer(&mut buffer).unwrap();
+        let parsed = parse_response_to(Method::Head, &buffer[..len], &mut []).unwrap();
+        assert_eq!(Some("5"), parsed.header("Content-Length"));
+        assert!(parsed.body().is_empty());
+
+        // Without knowing the method, the missing body looks truncated.
+        assert_eq!(Err(Error::TruncatedBody), parse(&buffer[..len]));
+    }
+
+    #[test]
+    fn parse_not_modified_ignores_content_length() {
+        let parsed = parse(b"HTTP/1.1 304 Not Modified\r\nContent-Length: 120\r\n\r\n").unwrap();
+        assert_eq!(Status::NotModified, parsed.status());
+        assert!(parsed.body().is_empty());
+    }
+
+    #[test]
+    fn parse_empty_reason_phrase() {
+        let parsed = parse(b"HTTP/1.0 200 \r\nContent-Length: 0\r\n\r\n").unwrap();
+        assert_eq!(HttpVersion::Http10, parsed.version());
+        assert_eq!(Status::Ok, parsed.status());
+        assert_eq!("", parsed.reason());
+    }
+
+    #[test]
+    fn parse_invalid_status_code_returns_err() {
+        assert_eq!(
+            Err(Error::InvalidStatusCode),
+            parse(b"HTTP/1.1 20 OK\r\n\r\n")
+        );
+        assert_eq!(
+            Err(Error::InvalidStatusCode),
+            parse(b"HTTP/1.1 099 Nope\r\n\r\n")
+        );
+    }
+
+    #[test]
+    fn parse_missing_space_returns_err() {
+        assert_eq!(Err(Error::ExpectedSpace), parse(b"HTTP/1.1 200\r\n\r\n"));
+    }
+
+    #[test]
+    fn parse_invalid_version_returns_err() {
+        assert_eq!(
+            Err(Error::Message(RequestError::InvalidHttpVersion)),
+            parse(b"HTTX/1.1 200 OK\r\n\r\n")
+        );
+    }
+
+    #[test]
+    fn parse_truncated_body_returns_err() {
+        assert_eq!(
+            Err(Error::TruncatedBody),
+            parse(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhel")
+        );
+    }
+
+    #[test]
+    fn parse_body_larger_than_request_limit() {
+        extern crate std;
+        use std::vec::Vec;
+
+        let body = [b'x'; 10_000];
+        let mut raw = Vec::from(&b"HTTP/1.1 200 OK\r\nContent-Length: 10000\r\n\r\n"[..]);
+        raw.extend_from_slice(&body);
+        assert_eq!(&body[..], parse(&raw).unwrap().body());
+
+        let mut raw = Vec::from(&b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n"[..]);
+        for chunk in body.chunks(4096) {
+            raw.extend_from_slice(std::format!("{:x}\r\n", chunk.len()).as_bytes());
+            raw.extend_from_slice(chunk);
+            raw.extend_from_slice(b"\r\n");
+        }
+        raw.extend_from_slice(b"0\r\n\r\n");
+        let mut body_buffer = [0; 10_000];
+        let parsed = parse_with_body_buffer(&raw, &mut body_buffer).unwrap();
+        assert_eq!(&body[..], parsed.body());
+    }
+
+    #[test]
+    fn parse_other_transfer_coding_reads_to_end() {
+        let parsed =
+            parse(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip\r\nContent-Length: 2\r\n\r\nabcdef")
+                .unwrap();
+        assert_eq!(b"abcdef", parsed.body());
+    }
+
+    #[test]
+    fn parse_chunked_overrides_content_length() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\n\r\n";
+        let mut body_buffer = [0; 8];
+        assert_eq!(
+            b"abc",
+            parse_with_body_buffer(raw, &mut body_buffer)
+                .unwrap()
+                .body()
+        );
+    }
+
+    #[test]
+    fn parse_invalid_content_length_returns_err() {
+        assert_eq!(
+            Err(Error::InvalidContentLength),
+            parse(b"HTTP/1.1 200 OK\r\nContent-Length: +5\r\n\r\nhello")
+        );
+        assert_eq!(
+            Err(Error::ConflictingBodyLength),
+            parse(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nContent-Length: 6\r\n\r\nhello!")
+        );
+    }
+
+    #[test]
+    fn parse_overflowing_chunk_size_returns_err() {
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n1FFFFFFFFFFFFFFFF\r\nabc\r\n0\r\n\r\n";
+        let mut body_buffer = [0; 8];
+        assert_eq!(
+            Err(Error::MalformedChunk),
+            parse_with_body_buffer(raw, &mut body_buffer)
+        );
+    }
+}
@@ -1,16 +1,236 @@
+macro_rules! statuses {
+    ($(($variant:ident, $code:expr, $reason:expr)),* $(,)?) => {
+        /// HTTP response status codes indicate whether a specific HTTP request has been successfully completed.
+        /// Covers the codes registered with IANA; anything else can be sent with `Status::custom`.
+        /// See https://developer.mozilla.org/en-US/docs/Web/HTTP/Status
+        #[derive(PartialEq, Copy, Clone, Debug)]
+        pub enum Status {
+            $($variant,)*
+            /// A status that isn't registered. Built with `Status::custom` or `Status::from_u16`, which only accept three digit codes.
+            Custom(CustomStatus),
+        }
+
+        impl Status {
+            /// The numeric status code.
+            pub fn code(&self) -> u16 {
+                match self {
+                    $(Status::$variant => $code,)*
+                    Status::Custom(custom) => custom.code,
+                }
+            }
+
+            /// The canonical reason phrase for the code.
+            pub fn reason(&self) -> &'static str {
+                match self {
+                    $(Status::$variant => $reason,)*
+                    Status::Custom(custom) => custom.reason,
+                }
+            }
+
+            /// Returns the status for the code. Registered codes map to their variant, other three digit codes to `Custom` with an empty reason.
+            pub fn from_u16(code: u16) -> Option<Self> {
+                match code {
+                    $($code => Some(Status::$variant),)*
+                    100..=999 => Some(Status::Custom(CustomStatus { code, reason: "" })),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+statuses!(
+    (Continue, 100, "Continue"),
+    (SwitchingProtocols, 101, "Switching Protocols"),
+    (Processing, 102, "Processing"),
+    (EarlyHints, 103, "Early Hints"),
+    (Ok, 200, "OK"),
+    (Created, 201, "Created"),
+    (Accepted, 202, "Accepted"),
+    (
+        NonAuthoritativeInformation,
+        203,
+        "Non-Authoritative Information"
+    ),
+    (NoContent, 204, "No Content"),
+    (ResetContent, 205, "Reset Content"),
+    (PartialContent, 206, "Partial Content"),
+    (MultiStatus, 207, "Multi-Status"),
+    (AlreadyReported, 208, "Already Reported"),
+    (ImUsed, 226, "IM Used"),
+    (MultipleChoices, 300, "Multiple Choices"),
+    (MovedPermanently, 301, "Moved Permanently"),
+    (Found, 302, "Found"),
+    (SeeOther, 303, "See Other"),
+    (NotModified, 304, "Not Modified"),
+    (UseProxy, 305, "Use Proxy"),
+    (TemporaryRedirect, 307, "Temporary Redirect"),
+    (PermanentRedirect, 308, "Permanent Redirect"),
+    (BadRequest, 400, "Bad Request"),
+    (Unauthorized, 401, "Unauthorized"),
+    (PaymentRequired, 402, "Payment Required"),
+    (Forbidden, 403, "Forbidden"),
+    (NotFound, 404, "Not Found"),
+    (MethodNotAllowed, 405, "Method Not Allowed"),
+    (NotAcceptable, 406, "Not Acceptable"),
+    (
+        ProxyAuthenticationRequired,
+        407,
+        "Proxy Authentication Required"
+    ),
+    (RequestTimeout, 408, "Request Timeout"),
+    (Conflict, 409, "Conflict"),
+    (Gone, 410, "Gone"),
+    (LengthRequired, 411, "Length Required"),
+    (PreconditionFailed, 412, "Precondition Failed"),
+    (ContentTooLarge, 413, "Content Too Large"),
+    (UriTooLong, 414, "URI Too Long"),
+    (UnsupportedMediaType, 415, "Unsupported Media Type"),
+    (RangeNotSatisfiable, 416, "Range Not Satisfiable"),
+    (ExpectationFailed, 417, "Expectation Failed"),
+    (MisdirectedRequest, 421, "Misdirected Request"),
+    (UnprocessableContent, 422, "Unprocessable Content"),
+    (Locked, 423, "Locked"),
+    (FailedDependency, 424, "Failed Dependency"),
+    (TooEarly, 425, "Too Early"),
+    (UpgradeRequired, 426, "Upgrade Required"),
+    (PreconditionRequired, 428, "Precondition Required"),
+    (TooManyRequests, 429, "Too Many Requests"),
+    (
+        RequestHeaderFieldsTooLarge,
+        431,
+        "Request Header Fields Too Large"
+    ),
+    (
+        UnavailableForLegalReasons,
+        451,
+        "Unavailable For Legal Reasons"
+    ),
+    (InternalServerError, 500, "Internal Server Error"),
+    (NotImplemented, 501, "Not Implemented"),
+    (BadGateway, 502, "Bad Gateway"),
+    (ServiceUnavailable, 503, "Service Unavailable"),
+    (GatewayTimeout, 504, "Gateway Timeout"),
+    (HttpVersionNotSupported, 505, "HTTP Version Not Supported"),
+    (VariantAlsoNegotiates, 506, "Variant Also Negotiates"),
+    (InsufficientStorage, 507, "Insufficient Storage"),
+    (LoopDetected, 508, "Loop Detected"),
+    (NotExtended, 510, "Not Extended"),
+    (
+        NetworkAuthenticationRequired,
+        511,
+        "Network Authentication Required"
+    ),
+);
+
+/// The code and reason phrase of a status that isn't registered.
+/// The fields are private so that the code always has the three digits a status line needs.
 #[derive(PartialEq, Copy, Clone, Debug)]
-pub enum Status {
-    Ok,
-    NotFound,
+pub struct CustomStatus {
+    code: u16,
+    reason: &'static str,
 }
 
 impl Status {
-    pub fn to_status(&self) -> &'static str {
-        match self {
-            Status::Ok => "200 OK",
-            Status::NotFound => "404 NOT FOUND",
+    /// A status that isn't registered, or `None` if the code doesn't have three digits, as a status line needs.
+    pub fn custom(code: u16, reason: &'static str) -> Option<Self> {
+        if (100..=999).contains(&code) {
+            Some(Status::Custom(CustomStatus { code, reason }))
+        } else {
+            None
+        }
+    }
+
+    /// The status as written on the status line, such as `200 OK`.
+    pub fn to_status(&self) -> StatusLine {
+        let code = self.code();
+        StatusLine {
+            code: [
+                b'0' + (code / 100 % 10) as u8,
+                b'0' + (code / 10 % 10) as u8,
+                b'0' + (code % 10) as u8,
+            ],
+            reason: self.reason(),
         }
     }
+
+    /// Whether a response with this status may have a body. 1xx, 204 and 304 responses never do.
+    pub fn allows_body(&self) -> bool {
+        !(self.is_informational() || *self == Status::NoContent || *self == Status::NotModified)
+    }
+
+    /// 1xx: the request was received and is being processed.
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.code())
+    }
+
+    /// 2xx: the request was successfully received, understood and accepted.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.code())
+    }
+
+    /// 3xx: further action is needed to complete the request.
+    pub fn is_redirect(&self) -> bool {
+        (300..400).contains(&self.code())
+    }
+
+    /// 4xx: the request contains bad syntax or cannot be fulfilled.
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.code())
+    }
+
+    /// 5xx: the server failed to fulfill an apparently valid request.
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.code())
+    }
+}
+
+/// The `code reason` part of a status line. Kept as parts so custom statuses don't need a buffer.
+#[derive(PartialEq, Copy, Clone)]
+pub struct StatusLine {
+    code: [u8; 3],
+    reason: &'static str,
+}
+
+impl StatusLine {
+    /// The three digit code as ASCII.
+    pub fn code(&self) -> &[u8] {
+        &self.code
+    }
+
+    pub fn reason(&self) -> &'static str {
+        self.reason
+    }
+}
+
+impl core::fmt::Display for StatusLine {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // The code is always made of ASCII digits.
+        let code = core::str::from_utf8(&self.code).unwrap();
+        write!(f, "{} {}", code, self.reason)
+    }
+}
+
+impl core::fmt::Debug for StatusLine {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "\"{}\"", self)
+    }
+}
+
+impl PartialEq<str> for StatusLine {
+    fn eq(&self, other: &str) -> bool {
+        let other = other.as_bytes();
+        other.len() == self.code.len() + 1 + self.reason.len()
+            && other[..3] == self.code
+            && other[3] == b' '
+            && &other[4..] == self.reason.as_bytes()
+    }
+}
+
+impl PartialEq<StatusLine> for &str {
+    fn eq(&self, other: &StatusLine) -> bool {
+        other == *self
+    }
 }
 
 #[cfg(test)]
@@ -26,6 +246,83 @@ mod tests {
     #[test]
     fn to_status_NotFound() {
         let status = Status::NotFound;
-        assert_eq!("404 NOT FOUND", status.to_status());
+        assert_eq!("404 Not Found", status.to_status());
+    }
+
+    #[test]
+    fn to_status_custom() {
+        let status = Status::custom(599, "Network Connect Timeout").unwrap();
+        assert_eq!("599 Network Connect Timeout", status.to_status());
+        assert_eq!(b"599", status.to_status().code());
+    }
+
+    #[test]
+    fn code_and_reason() {
+        assert_eq!(201, Status::Created.code());
+        assert_eq!("Created", Status::Created.reason());
+        assert_eq!(413, Status::ContentTooLarge.code());
+        assert_eq!("Service Unavailable", Status::ServiceUnavailable.reason());
+    }
+
+    #[test]
+    fn from_u16_registered_returns_variant() {
+        for code in [100, 204, 301, 304, 400, 401, 403, 405, 413, 500, 503].iter() {
+            let status = Status::from_u16(*code).unwrap();
+            assert_eq!(*code, status.code());
+            assert!(!matches!(status, Status::Custom(_)));
+        }
+
+        assert_eq!(Some(Status::NotModified), Status::from_u16(304));
+    }
+
+    #[test]
+    fn from_u16_unregistered_returns_custom() {
+        let status = Status::from_u16(299).unwrap();
+        assert!(matches!(status, Status::Custom(_)));
+        assert_eq!((299, ""), (status.code(), status.reason()));
+    }
+
+    #[test]
+    fn from_u16_out_of_range_returns_none() {
+        assert_eq!(None, Status::from_u16(99));
+        assert_eq!(None, Status::from_u16(1000));
+    }
+
+    #[test]
+    fn class_predicates() {
+        assert!(Status::Continue.is_informational());
+        assert!(Status::NoContent.is_success());
+        assert!(Status::MovedPermanently.is_redirect());
+        assert!(Status::MethodNotAllowed.is_client_error());
+        assert!(Status::BadGateway.is_server_error());
+        assert!(Status::custom(599, "").unwrap().is_server_error());
+
+        assert!(!Status::Ok.is_client_error());
+        assert!(!Status::NotModified.allows_body());
+        assert!(Status::ResetContent.allows_body());
+        assert!(!Status::NotFound.is_success());
+    }
+
+    #[test]
+    fn custom_rejects_codes_without_three_digits() {
+        let status = Status::custom(299, "Fine").unwrap();
+        assert_eq!((299, "Fine"), (status.code(), status.reason()));
+        assert_eq!(None, Status::custom(99, "Low"));
+        assert_eq!(None, Status::custom(1200, "High"));
+    }
+
+    #[test]
+    fn every_status_line_has_three_digits() {
+        // Doesn't rely on debug assertions, so holds in release builds too.
+        for code in 0..=u16::MAX {
+            for status in Status::from_u16(code)
+                .into_iter()
+                .chain(Status::custom(code, "x"))
+            {
+                let line = status.to_status();
+                let written = core::str::from_utf8(line.code()).unwrap();
+                assert_eq!(Ok(code), written.parse::<u16>());
+            }
+        }
     }
 }
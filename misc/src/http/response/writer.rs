@@ -0,0 +1,375 @@
+use crate::parser::StreamError;
+
+/// A destination for serialized responses, such as a socket.
+pub trait ResponseWriter {
+    /// Writes as many of the bytes as the sink can currently accept, returning how many were taken.
+    /// Returning `Ok(0)` for a non-empty slice signals backpressure: the sink is full for now.
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError>;
+
+    /// Pushes out anything the writer is holding on to. Returns `StreamError::WouldBlock` if the sink is still full.
+    fn flush(&mut self) -> Result<(), StreamError> {
+        Ok(())
+    }
+}
+
+impl<W: ResponseWriter + ?Sized> ResponseWriter for &mut W {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        (**self).write(bytes)
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        (**self).flush()
+    }
+}
+
+/// Writes into a fixed slice, failing with `StreamError::BufferOverflow` once it is full.
+pub struct SliceWriter<'b> {
+    buffer: &'b mut [u8],
+    len: usize,
+}
+
+impl<'b> SliceWriter<'b> {
+    pub fn new(buffer: &'b mut [u8]) -> Self {
+        Self { buffer, len: 0 }
+    }
+
+    /// The number of bytes written so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<'b> ResponseWriter for SliceWriter<'b> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        let end = self.len + bytes.len();
+        if end > self.buffer.len() {
+            return Err(StreamError::BufferOverflow {
+                max_bytes: self.buffer.len(),
+                actual_size: end,
+            });
+        }
+
+        self.buffer[self.len..end].copy_from_slice(bytes);
+        self.len = end;
+        Ok(bytes.len())
+    }
+}
+
+/// Counts the bytes written without storing them.
+#[derive(Default)]
+pub struct SizeCounter {
+    len: usize,
+}
+
+impl SizeCounter {
+    pub fn new() -> Self {
+        Self { len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl ResponseWriter for SizeCounter {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        self.len += bytes.len();
+        Ok(bytes.len())
+    }
+}
+
+/// Gathers the many small writes a response is made of into a bounded scratch buffer, passing them on to the inner writer as the buffer fills.
+/// Bytes taken into the scratch buffer count as written, so the same `BufferedWriter` must be kept when resuming after backpressure.
+pub struct BufferedWriter<'s, W: ResponseWriter> {
+    inner: W,
+    scratch: &'s mut [u8],
+    len: usize,
+}
+
+impl<'s, W: ResponseWriter> BufferedWriter<'s, W> {
+    pub fn new(inner: W, scratch: &'s mut [u8]) -> Self {
+        Self {
+            inner,
+            scratch,
+            len: 0,
+        }
+    }
+
+    /// The number of bytes waiting in the scratch buffer.
+    pub fn buffered(&self) -> usize {
+        self.len
+    }
+
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns the inner writer. Anything still in the scratch buffer is lost, so `flush` first.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Passes as much of the scratch buffer as possible to the inner writer, returning whether it was emptied.
+    fn drain(&mut self) -> Result<bool, StreamError> {
+        let mut written = 0;
+        while written < self.len {
+            match self.inner.write(&self.scratch[written..self.len])? {
+                0 => break,
+                n => written += n,
+            }
+        }
+
+        self.scratch.copy_within(written..self.len, 0);
+        self.len -= written;
+        Ok(self.len == 0)
+    }
+}
+
+impl<'s, W: ResponseWriter> ResponseWriter for BufferedWriter<'s, W> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        if self.len == self.scratch.len() {
+            self.drain()?;
+        }
+
+        // Nothing fits in the scratch buffer, so write straight through.
+        if self.scratch.is_empty() {
+            return self.inner.write(bytes);
+        }
+
+        let len = bytes.len().min(self.scratch.len() - self.len);
+        self.scratch[self.len..self.len + len].copy_from_slice(&bytes[..len]);
+        self.len += len;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        // The writer doesn't know where it is within the response; `Response::write_to` fills that in.
+        if !self.drain()? {
+            return Err(StreamError::WouldBlock { bytes_written: 0 });
+        }
+
+        self.inner.flush()
+    }
+}
+
+/// Writes to a `std::io::Write`, such as a `TcpStream`.
+/// Errors other than `WouldBlock` are kept and reported as `StreamError::WriteFailed`; fetch them with `take_error`.
+#[cfg(feature = "std")]
+pub struct IoWriter<W: std::io::Write> {
+    inner: W,
+    error: Option<std::io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> IoWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, error: None }
+    }
+
+    /// Returns the I/O error behind the last `StreamError::WriteFailed`.
+    pub fn take_error(&mut self) -> Option<std::io::Error> {
+        self.error.take()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn fail(&mut self, error: std::io::Error) -> StreamError {
+        self.error = Some(error);
+        StreamError::WriteFailed
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> ResponseWriter for IoWriter<W> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        use std::io::ErrorKind;
+
+        loop {
+            return match self.inner.write(bytes) {
+                Ok(0) if !bytes.is_empty() => {
+                    Err(self.fail(std::io::Error::from(ErrorKind::WriteZero)))
+                }
+                Ok(n) => Ok(n),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(0),
+                Err(e) => Err(self.fail(e)),
+            };
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        match self.inner.flush() {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                Err(StreamError::WouldBlock { bytes_written: 0 })
+            }
+            Err(e) => Err(self.fail(e)),
+        }
+    }
+}
+
+/// Tracks how far through a message the writes are, so that writing can resume part way through.
+pub(crate) struct PassOutput<W: ResponseWriter> {
+    writer: W,
+    /// Bytes before this position were written by an earlier call.
+    skip: usize,
+    position: usize,
+}
+
+impl<W: ResponseWriter> PassOutput<W> {
+    /// Starts writing a message, skipping the first `skip` bytes.
+    pub(crate) fn new(writer: W, skip: usize) -> Self {
+        Self {
+            writer,
+            skip,
+            position: 0,
+        }
+    }
+
+    /// How far into the message the writes have got, counting skipped bytes.
+    pub(crate) fn position(&self) -> usize {
+        self.position
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+        let start = self.position;
+        let already_written = self.skip.saturating_sub(start).min(bytes.len());
+        let mut remaining = &bytes[already_written..];
+        self.position += already_written;
+
+        while !remaining.is_empty() {
+            match self.writer.write(remaining)? {
+                0 => {
+                    return Err(StreamError::WouldBlock {
+                        bytes_written: self.position,
+                    })
+                }
+                n => {
+                    self.position += n;
+                    remaining = &remaining[n..];
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the writer once the whole message is written, returning its size.
+    pub(crate) fn finish(mut self) -> Result<usize, StreamError> {
+        let position = self.position;
+        match self.writer.flush() {
+            // The writer doesn't know the position within the message, so fill it in
+            Err(StreamError::WouldBlock { .. }) => Err(StreamError::WouldBlock {
+                bytes_written: position,
+            }),
+            result => result.map(|_| position),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Accepts at most `per_write` bytes per call and `capacity` bytes in total before blocking.
+    struct ThrottledSink {
+        data: [u8; 64],
+        len: usize,
+        per_write: usize,
+        capacity: usize,
+    }
+
+    impl ThrottledSink {
+        fn new(per_write: usize, capacity: usize) -> Self {
+            Self {
+                data: [0; 64],
+                len: 0,
+                per_write,
+                capacity,
+            }
+        }
+    }
+
+    impl ResponseWriter for ThrottledSink {
+        fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+            let len = bytes
+                .len()
+                .min(self.per_write)
+                .min(self.capacity - self.len);
+            self.data[self.len..self.len + len].copy_from_slice(&bytes[..len]);
+            self.len += len;
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn slice_writer_overflow_returns_err() {
+        let mut buffer = [0; 4];
+        let mut writer = SliceWriter::new(&mut buffer);
+
+        assert_eq!(Ok(3), writer.write(b"abc"));
+        assert_eq!(
+            Err(StreamError::BufferOverflow {
+                max_bytes: 4,
+                actual_size: 6
+            }),
+            writer.write(b"def")
+        );
+        assert_eq!(3, writer.len());
+    }
+
+    #[test]
+    fn buffered_writer_batches_small_writes() {
+        let mut scratch = [0; 8];
+        let mut writer = BufferedWriter::new(ThrottledSink::new(64, 64), &mut scratch);
+
+        for piece in [&b"ab"[..], b"cd", b"ef"].iter() {
+            assert_eq!(Ok(2), writer.write(piece));
+        }
+        assert_eq!(0, writer.inner().len);
+
+        writer.flush().unwrap();
+        assert_eq!(b"abcdef", &writer.inner().data[..6]);
+    }
+
+    #[test]
+    fn buffered_writer_handles_partial_inner_writes() {
+        let mut scratch = [0; 4];
+        let mut writer = BufferedWriter::new(ThrottledSink::new(3, 64), &mut scratch);
+
+        let mut written = 0;
+        let data = b"0123456789";
+        while written < data.len() {
+            written += writer.write(&data[written..]).unwrap();
+        }
+        writer.flush().unwrap();
+
+        assert_eq!(data, &writer.inner().data[..10]);
+    }
+
+    #[test]
+    fn buffered_writer_flush_reports_backpressure() {
+        let mut scratch = [0; 4];
+        let mut writer = BufferedWriter::new(ThrottledSink::new(64, 2), &mut scratch);
+
+        assert_eq!(Ok(4), writer.write(b"abcd"));
+        assert_eq!(
+            Err(StreamError::WouldBlock { bytes_written: 0 }),
+            writer.flush()
+        );
+
+        // Two bytes reached the sink, making room for two more before the writer blocks.
+        assert_eq!(Ok(2), writer.write(b"efg"));
+        assert_eq!(Ok(0), writer.write(b"g"));
+    }
+}
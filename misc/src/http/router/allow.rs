@@ -0,0 +1,53 @@
+use crate::http::request::Method;
+
+/// The set of methods a path accepts, used for the `Allow` header of a 405 response.
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
+pub struct Allow {
+    methods: u16,
+}
+
+impl Allow {
+    pub fn new() -> Self {
+        Self { methods: 0 }
+    }
+
+    pub fn insert(&mut self, method: Method) {
+        self.methods |= Self::bit(method);
+    }
+
+    pub fn contains(&self, method: Method) -> bool {
+        self.methods & Self::bit(method) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.methods == 0
+    }
+
+    /// Iterates over the allowed methods in declaration order.
+    pub fn iter(&self) -> impl Iterator<Item = Method> + '_ {
+        Method::ALL
+            .iter()
+            .copied()
+            .filter(move |method| self.contains(*method))
+    }
+
+    fn bit(method: Method) -> u16 {
+        let index = Method::ALL.iter().position(|m| *m == method).unwrap_or(0);
+        1 << index
+    }
+}
+
+/// Writes the methods as an `Allow` header value, such as `GET, HEAD, POST`.
+impl core::fmt::Display for Allow {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (i, method) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+
+            f.write_str(method.to_str())?;
+        }
+
+        Ok(())
+    }
+}
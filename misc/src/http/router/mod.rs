@@ -0,0 +1,362 @@
+mod allow;
+mod params;
+pub use allow::Allow;
+pub use params::{Param, Params, MAX_PARAMS};
+
+use crate::http::handler::Handler;
+use crate::http::request::{Method, Request};
+use crate::http::response::{Response, Status};
+
+/// Answers a request routed to it, given the params captured from the path.
+/// Implemented for every `fn(&Request, &Params) -> Response` and matching closure.
+pub trait RouteHandler {
+    fn handle<'r>(&self, request: &Request<'r>, params: &Params<'_>) -> Response<'r>;
+}
+
+impl<F> RouteHandler for F
+where
+    F: for<'r, 'p> Fn(&Request<'r>, &Params<'p>) -> Response<'r>,
+{
+    fn handle<'r>(&self, request: &Request<'r>, params: &Params<'_>) -> Response<'r> {
+        self(request, params)
+    }
+}
+
+/// A handler registered for a method and path pattern.
+///
+/// Patterns are made of `/` separated segments:
+/// * `users` matches the segment exactly.
+/// * `:id` matches any single non-empty segment and captures it as `id`.
+/// * `*rest` may only be the final segment and captures the remainder of the path, slashes included, as `rest`.
+#[derive(Copy, Clone, Debug)]
+pub struct Route<H> {
+    method: Method,
+    pattern: &'static str,
+    handler: H,
+}
+
+impl<H> Route<H> {
+    pub const fn new(method: Method, pattern: &'static str, handler: H) -> Self {
+        Self {
+            method,
+            pattern,
+            handler,
+        }
+    }
+
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    pub fn pattern(&self) -> &'static str {
+        self.pattern
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+}
+
+/// The outcome of routing a request.
+// The params are kept inline so routing never needs an allocator.
+#[allow(clippy::large_enum_variant)]
+#[derive(PartialEq, Debug)]
+pub enum RouteMatch<'r, 'p, H> {
+    /// A route matched both the path and the method.
+    Found { handler: &'r H, params: Params<'p> },
+    /// No route matched the path. Should be answered with a 404.
+    NotFound,
+    /// Routes matched the path, but none for the method. Should be answered with a 405 and the `Allow` header.
+    MethodNotAllowed { allow: Allow },
+}
+
+/// Dispatches requests over a fixed list of routes. Routes are tried in order, so earlier routes win.
+/// A `HEAD` request falls back to the matching `GET` route when there is no explicit `HEAD` route.
+#[derive(Copy, Clone, Debug)]
+pub struct Router<'r, H> {
+    routes: &'r [Route<H>],
+}
+
+impl<'r, H> Router<'r, H> {
+    pub const fn new(routes: &'r [Route<H>]) -> Self {
+        Self { routes }
+    }
+
+    /// Finds the handler for the method and route. The route should be the path without its query, e.g. from `Path::route`.
+    pub fn route<'p>(&self, method: Method, route: &'p str) -> RouteMatch<'r, 'p, H> {
+        let mut allow = Allow::new();
+        let mut head_fallback = None;
+
+        for candidate in self.routes {
+            let params = match match_pattern(candidate.pattern, route) {
+                Some(params) => params,
+                None => continue,
+            };
+
+            if candidate.method == method {
+                return RouteMatch::Found {
+                    handler: &candidate.handler,
+                    params,
+                };
+            }
+
+            if method == Method::Head && candidate.method == Method::Get && head_fallback.is_none()
+            {
+                head_fallback = Some((&candidate.handler, params));
+            }
+
+            allow.insert(candidate.method);
+            if candidate.method == Method::Get {
+                allow.insert(Method::Head);
+            }
+        }
+
+        if let Some((handler, params)) = head_fallback {
+            return RouteMatch::Found { handler, params };
+        }
+
+        if allow.is_empty() {
+            RouteMatch::NotFound
+        } else {
+            RouteMatch::MethodNotAllowed { allow }
+        }
+    }
+}
+
+/// Answers requests with the routed handler, a 404 when no route matches the path,
+/// or a 405 with an `Allow` header when none matches the method.
+impl<'t, H: RouteHandler> Handler for Router<'t, H> {
+    fn handle<'r>(&self, request: &Request<'r>) -> Response<'r> {
+        let path = request.path();
+        match self.route(request.method(), path.route()) {
+            RouteMatch::Found { handler, params } => handler.handle(request, &params),
+            RouteMatch::NotFound => Response::new(Status::NotFound),
+            RouteMatch::MethodNotAllowed { allow } => {
+                let mut response = Response::new(Status::MethodNotAllowed);
+                // The response is still a valid 405 without the header.
+                let _ = response.set_formatted_header("Allow", format_args!("{}", allow));
+                response
+            }
+        }
+    }
+}
+
+/// Matches the route against the pattern, returning the captured params on success.
+fn match_pattern<'p>(pattern: &'static str, route: &'p str) -> Option<Params<'p>> {
+    let mut params = Params::new();
+    let mut patterns = pattern.split('/');
+    let mut remaining = route;
+
+    loop {
+        let segment_pattern = patterns.next();
+
+        if let Some(name) = segment_pattern.and_then(|p| p.strip_prefix('*')) {
+            return if params.push(name, remaining) {
+                Some(params)
+            } else {
+                None
+            };
+        }
+
+        let (segment, rest) = match remaining.find('/') {
+            Some(index) => (&remaining[..index], Some(&remaining[index + 1..])),
+            None => (remaining, None),
+        };
+
+        let segment_pattern = segment_pattern?;
+        match segment_pattern.strip_prefix(':') {
+            Some(name) => {
+                if segment.is_empty() || !params.push(name, segment) {
+                    return None;
+                }
+            }
+            None if segment_pattern == segment => {}
+            None => return None,
+        }
+
+        match rest {
+            Some(rest) => remaining = rest,
+            None => {
+                return match patterns.next().map(|p| p.strip_prefix('*')) {
+                    None => Some(params),
+                    // A trailing wildcard may capture nothing.
+                    Some(Some(name)) if patterns.next().is_none() && params.push(name, "") => {
+                        Some(params)
+                    }
+                    Some(_) => None,
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use std::string::ToString;
+
+    #[derive(PartialEq, Debug)]
+    enum Handler {
+        Index,
+        ListUsers,
+        CreateUser,
+        GetUser,
+        GetPost,
+        Assets,
+        Health,
+        HeadHealth,
+    }
+
+    const ROUTES: [Route<Handler>; 8] = [
+        Route::new(Method::Get, "/", Handler::Index),
+        Route::new(Method::Get, "/users", Handler::ListUsers),
+        Route::new(Method::Post, "/users", Handler::CreateUser),
+        Route::new(Method::Get, "/users/:id", Handler::GetUser),
+        Route::new(Method::Get, "/users/:id/posts/:post", Handler::GetPost),
+        Route::new(Method::Get, "/assets/*file", Handler::Assets),
+        Route::new(Method::Get, "/health", Handler::Health),
+        Route::new(Method::Head, "/health", Handler::HeadHealth),
+    ];
+
+    fn found<'r, 'p>(result: RouteMatch<'r, 'p, Handler>) -> (&'r Handler, Params<'p>) {
+        match result {
+            RouteMatch::Found { handler, params } => (handler, params),
+            other => panic!("expected a match, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn route_static_returns_handler() {
+        let router = Router::new(&ROUTES);
+
+        let (handler, params) = found(router.route(Method::Get, "/"));
+        assert_eq!(&Handler::Index, handler);
+        assert!(params.is_empty());
+
+        let (handler, _) = found(router.route(Method::Get, "/users"));
+        assert_eq!(&Handler::ListUsers, handler);
+    }
+
+    #[test]
+    fn route_dispatches_on_method() {
+        let router = Router::new(&ROUTES);
+
+        let (handler, _) = found(router.route(Method::Post, "/users"));
+        assert_eq!(&Handler::CreateUser, handler);
+    }
+
+    #[test]
+    fn route_named_segments_return_params() {
+        let router = Router::new(&ROUTES);
+
+        let (handler, params) = found(router.route(Method::Get, "/users/42"));
+        assert_eq!(&Handler::GetUser, handler);
+        assert_eq!(Some("42"), params.get("id"));
+
+        let (handler, params) = found(router.route(Method::Get, "/users/42/posts/7"));
+        assert_eq!(&Handler::GetPost, handler);
+        assert_eq!(2, params.len());
+        assert_eq!(Some("42"), params.get("id"));
+        assert_eq!(Some("7"), params.get("post"));
+    }
+
+    #[test]
+    fn route_named_segment_must_not_be_empty() {
+        let router = Router::new(&ROUTES);
+        assert_eq!(RouteMatch::NotFound, router.route(Method::Get, "/users/"));
+    }
+
+    #[test]
+    fn route_wildcard_captures_tail() {
+        let router = Router::new(&ROUTES);
+
+        let (handler, params) = found(router.route(Method::Get, "/assets/js/app.wasm"));
+        assert_eq!(&Handler::Assets, handler);
+        assert_eq!(Some("js/app.wasm"), params.get("file"));
+
+        let (_, params) = found(router.route(Method::Get, "/assets/"));
+        assert_eq!(Some(""), params.get("file"));
+
+        let (_, params) = found(router.route(Method::Get, "/assets"));
+        assert_eq!(Some(""), params.get("file"));
+    }
+
+    #[test]
+    fn route_unknown_path_returns_not_found() {
+        let router = Router::new(&ROUTES);
+
+        assert_eq!(RouteMatch::NotFound, router.route(Method::Get, "/missing"));
+        assert_eq!(
+            RouteMatch::NotFound,
+            router.route(Method::Get, "/users/42/extra")
+        );
+    }
+
+    #[test]
+    fn route_wrong_method_returns_method_not_allowed() {
+        let router = Router::new(&ROUTES);
+
+        let allow = match router.route(Method::Delete, "/users") {
+            RouteMatch::MethodNotAllowed { allow } => allow,
+            other => panic!("expected method not allowed, got {:?}", other),
+        };
+
+        assert!(allow.contains(Method::Get));
+        assert!(allow.contains(Method::Head));
+        assert!(allow.contains(Method::Post));
+        assert!(!allow.contains(Method::Delete));
+        assert_eq!("GET, HEAD, POST", allow.to_string());
+    }
+
+    #[test]
+    fn route_head_falls_back_to_get() {
+        let router = Router::new(&ROUTES);
+
+        let (handler, params) = found(router.route(Method::Head, "/users/42"));
+        assert_eq!(&Handler::GetUser, handler);
+        assert_eq!(Some("42"), params.get("id"));
+    }
+
+    #[test]
+    fn route_explicit_head_wins_over_get() {
+        let router = Router::new(&ROUTES);
+
+        let (handler, _) = found(router.route(Method::Head, "/health"));
+        assert_eq!(&Handler::HeadHealth, handler);
+    }
+
+    type Respond = for<'r, 'p> fn(&Request<'r>, &Params<'p>) -> Response<'r>;
+
+    fn get_user<'r>(_request: &Request<'r>, params: &Params<'_>) -> Response<'r> {
+        let mut response = Response::new(Status::Ok);
+        response
+            .with_formatted_header("X-User", format_args!("{}", params.get("id").unwrap()))
+            .unwrap();
+        response
+    }
+
+    const RESPONDERS: [Route<Respond>; 1] = [Route::new(Method::Get, "/users/:id", get_user)];
+
+    #[test]
+    fn router_handles_requests() {
+        // The trait is shadowed by the `Handler` enum above.
+        use crate::http::handler::Handler as _;
+        use crate::http::request::parse;
+
+        let router = Router::new(&RESPONDERS);
+
+        let request = parse(b"GET /users/42?full=1 HTTP/1.1\r\n\r\n").unwrap();
+        let response = router.handle(&request);
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(Some("42"), response.header("X-User"));
+
+        let request = parse(b"DELETE /users/42 HTTP/1.1\r\n\r\n").unwrap();
+        let response = router.handle(&request);
+        assert_eq!(Status::MethodNotAllowed, response.status());
+        assert_eq!(Some("GET, HEAD"), response.header("Allow"));
+
+        let request = parse(b"GET /posts HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(Status::NotFound, router.handle(&request).status());
+    }
+}
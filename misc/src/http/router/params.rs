@@ -0,0 +1,74 @@
+pub const MAX_PARAMS: usize = 8;
+
+/// A value captured from a path by a `:name` segment or a `*name` tail.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Param<'p> {
+    name: &'static str,
+    value: &'p str,
+}
+
+impl<'p> Param<'p> {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The captured value. It is still percent-encoded.
+    pub fn value(&self) -> &'p str {
+        self.value
+    }
+}
+
+/// Fixed capacity list of the params captured while matching a route, in the order they appear in the pattern.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Params<'p> {
+    params: [Param<'p>; MAX_PARAMS],
+    len: usize,
+}
+
+impl<'p> Params<'p> {
+    pub fn new() -> Self {
+        Self {
+            params: [Param {
+                name: "",
+                value: "",
+            }; MAX_PARAMS],
+            len: 0,
+        }
+    }
+
+    /// Returns the value captured for the given name.
+    pub fn get(&self, name: &str) -> Option<&'p str> {
+        self.iter()
+            .find(|param| param.name == name)
+            .map(|param| param.value)
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, Param<'p>> {
+        self.params[..self.len].iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds a param, returning `false` if there is no room left.
+    pub(super) fn push(&mut self, name: &'static str, value: &'p str) -> bool {
+        if self.len >= MAX_PARAMS {
+            return false;
+        }
+
+        self.params[self.len] = Param { name, value };
+        self.len += 1;
+        true
+    }
+}
+
+impl<'p> Default for Params<'p> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
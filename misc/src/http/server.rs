@@ -0,0 +1,395 @@
+//! A blocking HTTP/1.1 server over `std::net`. Only available with the `std` feature.
+
+use crate::http::handler::Handler;
+use crate::http::request::{HttpVersion, ParseStatus, RequestParser};
+use crate::http::response::{BufferedWriter, Header, IoWriter, Response, Status, StreamError};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+
+/// The most read from the socket at once.
+const READ_BYTES: usize = 4096;
+/// Size of the buffer responses are gathered in before being written to the socket.
+const WRITE_BUFFER_BYTES: usize = 4096;
+
+/// Requests served on one connection before it is closed, unless changed with `with_max_requests`.
+pub const MAX_REQUESTS: usize = 100;
+/// How long a connection may sit idle before it is closed, unless changed with `with_idle_timeout`.
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Accepts connections and answers each request with the response from a handler.
+/// Connections are kept open between requests. `serve` gives each connection its own thread, so a client holding one open doesn't keep others waiting.
+pub struct Server {
+    listener: TcpListener,
+    max_requests: usize,
+    idle_timeout: Option<Duration>,
+}
+
+impl Server {
+    /// Listens on the address. Binding port 0 picks a free port; see `local_addr`.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            max_requests: MAX_REQUESTS,
+            idle_timeout: Some(IDLE_TIMEOUT),
+        })
+    }
+
+    /// Closes a connection after this many requests. Zero is treated as one.
+    pub fn with_max_requests(mut self, max_requests: usize) -> Self {
+        self.max_requests = max_requests;
+        self
+    }
+
+    /// Closes a connection once the client has sent nothing for this long. `None` waits forever.
+    /// A client that goes quiet part way through a request is answered with 408 first.
+    pub fn with_idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serves connections until the process ends, each on a thread of its own that ends with the connection.
+    /// A failed connection is dropped without stopping the server.
+    pub fn serve<H>(&self, handler: H)
+    where
+        H: Handler + Sync,
+    {
+        let handler = &handler;
+        thread::scope(|scope| {
+            for stream in self.listener.incoming().flatten() {
+                scope.spawn(move || {
+                    let _ = self.serve_stream(stream, handler);
+                });
+            }
+        });
+    }
+
+    /// Accepts a single connection and serves it on the calling thread.
+    pub fn accept<H>(&self, handler: &H) -> io::Result<()>
+    where
+        H: Handler,
+    {
+        let (stream, _) = self.listener.accept()?;
+        self.serve_stream(stream, handler)
+    }
+
+    fn serve_stream<H>(&self, stream: TcpStream, handler: &H) -> io::Result<()>
+    where
+        H: Handler,
+    {
+        stream.set_read_timeout(self.idle_timeout)?;
+        serve_connection(stream, handler, self.max_requests)
+    }
+}
+
+/// Answers requests from the connection with the handler's responses until either side asks to close it,
+/// `max_requests` have been served, or the client goes quiet.
+/// Pipelined requests are answered in order from what has already been read.
+/// A chunked body is sent to an HTTP/1.0 client as is, ending with the connection.
+/// A request that fails to parse is answered with the status for the error, such as 400 or 413, and the connection closed.
+/// A connection closed or timed out between requests is closed without an answer, but one that times out part way through a request is answered with 408.
+pub fn serve_connection<S, H>(mut stream: S, handler: &H, max_requests: usize) -> io::Result<()>
+where
+    S: Read + Write,
+    H: Handler,
+{
+    let mut parser = RequestParser::new();
+    let mut chunk = [0; READ_BYTES];
+    let mut served = 0;
+    // After a response, the parser may already hold the next request.
+    let mut buffered = false;
+
+    loop {
+        let len = if buffered {
+            0
+        } else {
+            match stream.read(&mut chunk) {
+                Ok(0) => return Ok(()),
+                Ok(len) => len,
+                // The read timeout ran out.
+                Err(error)
+                    if error.kind() == io::ErrorKind::WouldBlock
+                        || error.kind() == io::ErrorKind::TimedOut =>
+                {
+                    if parser.buffered() == 0 {
+                        return Ok(());
+                    }
+                    let timeout = Response::new(Status::RequestTimeout);
+                    return write_response(&mut stream, close(timeout));
+                }
+                Err(error) => return Err(error),
+            }
+        };
+
+        match parser.feed(&chunk[..len]) {
+            Ok(ParseStatus::Incomplete) => buffered = false,
+            Ok(ParseStatus::Complete(request)) => {
+                served += 1;
+                let mut response = handler.handle(&request);
+                response.with_request_method(request.method());
+
+                // HTTP/1.0 clients can't decode chunked bodies, so the body is ended by closing the connection instead.
+                let until_close = request.version() == HttpVersion::Http10 && response.is_chunked();
+                if until_close {
+                    response.delimit_by_close();
+                }
+
+                let keep_alive = request.keep_alive()
+                    && !closes(&response)
+                    && !until_close
+                    && served < max_requests;
+                if !keep_alive {
+                    return write_response(&mut stream, close(response));
+                }
+                if request.version() == HttpVersion::Http10 {
+                    // HTTP/1.0 clients only keep the connection open when told so.
+                    let _ = response.set_header(Header {
+                        key: "Connection",
+                        value: "keep-alive",
+                    });
+                }
+                write_response(&mut stream, response)?;
+                buffered = true;
+            }
+            Err(error) => {
+                return write_response(&mut stream, close(Response::new(error.status())));
+            }
+        }
+    }
+}
+
+/// Whether the handler asked for the connection to be closed.
+fn closes(response: &Response) -> bool {
+    response.header("Connection").is_some_and(|value| {
+        value
+            .split(',')
+            .any(|option| option.trim().eq_ignore_ascii_case("close"))
+    })
+}
+
+/// Tells the client the connection is closed after the response.
+fn close(mut response: Response) -> Response {
+    // With every header slot taken the client still sees the connection close, just without warning.
+    let _ = response.set_header(Header {
+        key: "Connection",
+        value: "close",
+    });
+    response
+}
+
+fn write_response<S: Write>(stream: &mut S, response: Response) -> io::Result<()> {
+    let mut writer = IoWriter::new(stream);
+    let mut scratch = [0; WRITE_BUFFER_BYTES];
+
+    match response.write_to(BufferedWriter::new(&mut writer, &mut scratch)) {
+        Ok(_) => Ok(()),
+        Err(StreamError::WriteFailed) => Err(writer
+            .take_error()
+            .unwrap_or_else(|| io::ErrorKind::Other.into())),
+        // Only a non-blocking socket pushes back, and those aren't supported.
+        Err(_) => Err(io::ErrorKind::WouldBlock.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::request::Request;
+    use crate::http::response::{parse, BodyProducer};
+    use std::vec::Vec;
+
+    /// Sends `hello, world` in two chunks.
+    struct HelloWorld;
+
+    impl BodyProducer for HelloWorld {
+        fn chunk<'s>(&'s self, index: usize, _buffer: &'s mut [u8]) -> Option<&'s [u8]> {
+            [&b"hello, "[..], b"world"].get(index).copied()
+        }
+    }
+
+    fn echo<'r>(request: &Request<'r>) -> Response<'r> {
+        if request.path().to_str() == "/chunked" {
+            let mut response = Response::new(Status::Ok);
+            response.with_chunked_body(&HelloWorld);
+            return response;
+        }
+
+        let mut response = Response::new(Status::Ok);
+        response.with_body(request.body()).unwrap();
+        response
+            .with_header(Header {
+                key: "X-Method",
+                value: request.method().to_str(),
+            })
+            .unwrap();
+        response
+    }
+
+    /// Serves one connection on an ephemeral port, sending `raw` and returning everything the server wrote back.
+    fn exchange(raw: &'static [u8]) -> Vec<u8> {
+        exchange_with(Server::bind("127.0.0.1:0").unwrap(), raw)
+    }
+
+    fn exchange_with(server: Server, raw: &'static [u8]) -> Vec<u8> {
+        let addr = server.local_addr().unwrap();
+        let handle = thread::spawn(move || server.accept(&echo));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(raw).unwrap();
+        let mut out = Vec::new();
+        stream.read_to_end(&mut out).unwrap();
+
+        handle.join().unwrap().unwrap();
+        out
+    }
+
+    fn count(haystack: &[u8], needle: &[u8]) -> usize {
+        haystack
+            .windows(needle.len())
+            .filter(|window| *window == needle)
+            .count()
+    }
+
+    #[test]
+    fn serves_request_over_tcp() {
+        let out =
+            exchange(b"POST /echo HTTP/1.1\r\nConnection: close\r\nContent-Length: 5\r\n\r\nhello");
+        let response = parse(&out).unwrap();
+
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(b"hello", response.body());
+        assert_eq!(Some("POST"), response.header("X-Method"));
+        assert_eq!(Some("close"), response.header("Connection"));
+    }
+
+    #[test]
+    fn head_request_gets_no_body() {
+        let out =
+            exchange(b"HEAD /echo HTTP/1.1\r\nConnection: close\r\nContent-Length: 2\r\n\r\nhi");
+        assert!(out.ends_with(b"Connection: close\r\n\r\n"));
+    }
+
+    #[test]
+    fn malformed_request_gets_bad_request() {
+        let out = exchange(b"GET / HTTP/1.1\r\nno colon\r\n\r\n");
+        let response = parse(&out).unwrap();
+
+        assert_eq!(Status::BadRequest, response.status());
+        assert_eq!(Some("close"), response.header("Connection"));
+    }
+
+    #[test]
+    fn unsupported_version_gets_505() {
+        let out = exchange(b"GET / HTTP/2.0\r\n\r\n");
+        assert_eq!(
+            Status::HttpVersionNotSupported,
+            parse(&out).unwrap().status()
+        );
+    }
+
+    #[test]
+    fn serves_pipelined_requests_until_close() {
+        let out = exchange(
+            b"POST /a HTTP/1.1\r\nContent-Length: 3\r\n\r\none\
+              POST /b HTTP/1.1\r\nContent-Length: 3\r\n\r\ntwo\
+              GET /c HTTP/1.1\r\nConnection: close\r\n\r\n",
+        );
+
+        assert_eq!(3, count(&out, b"HTTP/1.1 200 OK"));
+        assert_eq!(1, count(&out, b"Connection: close"));
+        let first = parse(&out).unwrap();
+        assert_eq!(b"one", first.body());
+        assert_eq!(None, first.header("Connection"));
+        assert!(out.ends_with(b"Connection: close\r\n\r\n"));
+    }
+
+    #[test]
+    fn closes_after_max_requests() {
+        let server = Server::bind("127.0.0.1:0").unwrap().with_max_requests(2);
+        let out = exchange_with(
+            server,
+            b"GET /a HTTP/1.1\r\n\r\nGET /b HTTP/1.1\r\n\r\nGET /c HTTP/1.1\r\n\r\n",
+        );
+
+        assert_eq!(2, count(&out, b"HTTP/1.1 200 OK"));
+        assert!(out.ends_with(b"Connection: close\r\n\r\n"));
+    }
+
+    #[test]
+    fn http10_closes_unless_asked_to_keep_alive() {
+        let out = exchange(
+            b"GET /a HTTP/1.0\r\nConnection: keep-alive\r\n\r\nGET /b HTTP/1.0\r\n\r\n\
+              GET /c HTTP/1.0\r\n\r\n",
+        );
+
+        assert_eq!(2, count(&out, b"HTTP/1.1 200 OK"));
+        assert_eq!(
+            Some("keep-alive"),
+            parse(&out).unwrap().header("Connection")
+        );
+        assert!(out.ends_with(b"Connection: close\r\n\r\n"));
+    }
+
+    #[test]
+    fn idle_connection_is_closed() {
+        let server = Server::bind("127.0.0.1:0")
+            .unwrap()
+            .with_idle_timeout(Some(Duration::from_millis(50)));
+        let out = exchange_with(server, b"GET / HTTP/1.1\r\n\r\n");
+
+        assert_eq!(1, count(&out, b"HTTP/1.1 200 OK"));
+        assert_eq!(None, parse(&out).unwrap().header("Connection"));
+    }
+
+    #[test]
+    fn stalled_request_gets_408() {
+        let server = Server::bind("127.0.0.1:0")
+            .unwrap()
+            .with_idle_timeout(Some(Duration::from_millis(50)));
+        let out = exchange_with(server, b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhel");
+        let response = parse(&out).unwrap();
+
+        assert_eq!(Status::RequestTimeout, response.status());
+        assert_eq!(Some("close"), response.header("Connection"));
+    }
+
+    #[test]
+    fn serve_answers_other_connections_while_one_stays_open() {
+        let server = Server::bind("127.0.0.1:0").unwrap().with_idle_timeout(None);
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || server.serve(echo));
+
+        // Left open and idle, which would block a server taking connections one at a time
+        let mut idle = TcpStream::connect(addr).unwrap();
+        idle.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        let mut out = Vec::new();
+        stream.read_to_end(&mut out).unwrap();
+        assert_eq!(Status::Ok, parse(&out).unwrap().status());
+    }
+
+    #[test]
+    fn http10_chunked_response_is_ended_by_close() {
+        let out = exchange(
+            b"GET /chunked HTTP/1.0\r\nConnection: keep-alive\r\n\r\nGET /a HTTP/1.0\r\n\r\n",
+        );
+
+        assert_eq!(1, count(&out, b"HTTP/1.1 200 OK"));
+        assert_eq!(0, count(&out, b"Transfer-Encoding"));
+        let response = parse(&out).unwrap();
+        assert_eq!(Some("close"), response.header("Connection"));
+        assert_eq!(b"hello, world", response.body());
+    }
+}
@@ -0,0 +1,287 @@
+use super::error::Error;
+use super::tokenizer::{JsonStr, Token, Tokenizer};
+
+/// A value that can pull itself out of a JSON document.
+///
+/// Structs usually call `begin_object`, then loop over `next_key`, decoding the fields they know and calling `skip_value` for the rest.
+pub trait FromJson<'a>: Sized {
+    fn from_json(tokens: &mut Tokenizer<'a>) -> Result<Self, Error>;
+}
+
+/// Decodes a whole document. Anything but whitespace after the value is an error.
+pub fn from_slice<'a, T: FromJson<'a>>(data: &'a [u8]) -> Result<T, Error> {
+    let mut tokens = Tokenizer::new(data);
+    let value = T::from_json(&mut tokens)?;
+
+    match tokens.next_token()? {
+        None => Ok(value),
+        Some(_) => Err(Error::UnexpectedToken {
+            offset: tokens.token_offset(),
+        }),
+    }
+}
+
+impl<'a> Tokenizer<'a> {
+    /// Decodes the next value.
+    pub fn decode<T: FromJson<'a>>(&mut self) -> Result<T, Error> {
+        T::from_json(self)
+    }
+
+    /// Consumes the `{` that starts an object.
+    pub fn begin_object(&mut self) -> Result<(), Error> {
+        match self.expect_token()? {
+            Token::BeginObject => Ok(()),
+            _ => Err(self.unexpected_token()),
+        }
+    }
+
+    /// Returns the key of the next member, or `None` after consuming the `}` that ends the object.
+    pub fn next_key(&mut self) -> Result<Option<JsonStr<'a>>, Error> {
+        match self.expect_token()? {
+            Token::Key(key) => Ok(Some(key)),
+            Token::EndObject => Ok(None),
+            _ => Err(self.unexpected_token()),
+        }
+    }
+
+    /// Consumes the `[` that starts an array.
+    pub fn begin_array(&mut self) -> Result<(), Error> {
+        match self.expect_token()? {
+            Token::BeginArray => Ok(()),
+            _ => Err(self.unexpected_token()),
+        }
+    }
+
+    /// Whether another item follows in the array. Consumes the `]` that ends it when there are no more.
+    pub fn has_next_item(&mut self) -> Result<bool, Error> {
+        if self.peek()? == Some(Token::EndArray) {
+            self.expect_token()?;
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// Skips the next value, along with everything nested in it.
+    pub fn skip_value(&mut self) -> Result<(), Error> {
+        let mut depth = 0;
+        loop {
+            match self.expect_token()? {
+                Token::BeginObject | Token::BeginArray => depth += 1,
+                Token::EndObject | Token::EndArray => depth -= 1,
+                Token::Key(_) => continue,
+                _ => {}
+            }
+
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// The next token, treating the end of the document as an error.
+    fn expect_token(&mut self) -> Result<Token<'a>, Error> {
+        self.next_token()?.ok_or(Error::UnexpectedEndOfInput {
+            offset: self.offset(),
+        })
+    }
+
+    fn unexpected_token(&self) -> Error {
+        Error::UnexpectedToken {
+            offset: self.token_offset(),
+        }
+    }
+
+    /// The next token as a number.
+    fn number_token(&mut self) -> Result<&'a str, Error> {
+        match self.expect_token()? {
+            Token::Number(number) => Ok(number),
+            _ => Err(self.unexpected_token()),
+        }
+    }
+}
+
+impl<'a> FromJson<'a> for bool {
+    fn from_json(tokens: &mut Tokenizer<'a>) -> Result<Self, Error> {
+        match tokens.expect_token()? {
+            Token::Bool(value) => Ok(value),
+            _ => Err(tokens.unexpected_token()),
+        }
+    }
+}
+
+/// Strings that may contain escapes; see `JsonStr::decode`.
+impl<'a> FromJson<'a> for JsonStr<'a> {
+    fn from_json(tokens: &mut Tokenizer<'a>) -> Result<Self, Error> {
+        match tokens.expect_token()? {
+            Token::String(value) => Ok(value),
+            _ => Err(tokens.unexpected_token()),
+        }
+    }
+}
+
+/// Strings are borrowed from the document, so one with escapes is rejected with `Error::UnexpectedToken`. Decode those as `JsonStr`.
+impl<'a> FromJson<'a> for &'a str {
+    fn from_json(tokens: &mut Tokenizer<'a>) -> Result<Self, Error> {
+        JsonStr::from_json(tokens)?
+            .as_str()
+            .ok_or_else(|| tokens.unexpected_token())
+    }
+}
+
+/// `null` decodes as `None`.
+impl<'a, T: FromJson<'a>> FromJson<'a> for Option<T> {
+    fn from_json(tokens: &mut Tokenizer<'a>) -> Result<Self, Error> {
+        if tokens.peek()? == Some(Token::Null) {
+            tokens.expect_token()?;
+            return Ok(None);
+        }
+
+        T::from_json(tokens).map(Some)
+    }
+}
+
+macro_rules! from_json_numbers {
+    ($(($number:ty, $integer:expr)),*) => {
+        $(
+            impl<'a> FromJson<'a> for $number {
+                fn from_json(tokens: &mut Tokenizer<'a>) -> Result<Self, Error> {
+                    let number = tokens.number_token()?;
+
+                    // A fraction or exponent is not an integer, even if its value is whole.
+                    if $integer && number.bytes().any(|b| matches!(b, b'.' | b'e' | b'E')) {
+                        return Err(tokens.unexpected_token());
+                    }
+
+                    number.parse().map_err(|_| Error::NumberOutOfRange {
+                        offset: tokens.token_offset(),
+                    })
+                }
+            }
+        )*
+    };
+}
+
+from_json_numbers!(
+    (u8, true),
+    (u16, true),
+    (u32, true),
+    (u64, true),
+    (usize, true),
+    (i8, true),
+    (i16, true),
+    (i32, true),
+    (i64, true),
+    (isize, true),
+    (f32, false),
+    (f64, false)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Debug)]
+    struct User<'a> {
+        id: u32,
+        name: JsonStr<'a>,
+        email: Option<&'a str>,
+        scores: [i16; 4],
+        score_count: usize,
+    }
+
+    impl<'a> FromJson<'a> for User<'a> {
+        fn from_json(tokens: &mut Tokenizer<'a>) -> Result<Self, Error> {
+            let mut id = None;
+            let mut name = None;
+            let mut email = None;
+            let mut scores = [0; 4];
+            let mut score_count = 0;
+
+            tokens.begin_object()?;
+            while let Some(key) = tokens.next_key()? {
+                if key.matches("id") {
+                    id = Some(tokens.decode()?);
+                } else if key.matches("name") {
+                    name = Some(tokens.decode()?);
+                } else if key.matches("email") {
+                    email = tokens.decode()?;
+                } else if key.matches("scores") {
+                    tokens.begin_array()?;
+                    while tokens.has_next_item()? {
+                        scores[score_count] = tokens.decode()?;
+                        score_count += 1;
+                    }
+                } else {
+                    tokens.skip_value()?;
+                }
+            }
+
+            Ok(User {
+                id: id.ok_or(Error::MissingField { name: "id" })?,
+                name: name.ok_or(Error::MissingField { name: "name" })?,
+                email,
+                scores,
+                score_count,
+            })
+        }
+    }
+
+    #[test]
+    fn from_slice_decodes_struct() {
+        let data = br#"{"id": 7, "extra": {"nested": [1, {"a": null}]}, "name": "Ada \"L\"", "email": null, "scores": [3, -4]}"#;
+        let user: User = from_slice(data).unwrap();
+
+        assert_eq!(7, user.id);
+        assert!(user.name.matches("Ada \"L\""));
+        assert_eq!(None, user.email);
+        assert_eq!([3, -4], user.scores[..user.score_count]);
+    }
+
+    #[test]
+    fn from_slice_missing_field_returns_err() {
+        assert_eq!(
+            Err(Error::MissingField { name: "name" }),
+            from_slice::<User>(br#"{"id": 1}"#)
+        );
+    }
+
+    #[test]
+    fn from_slice_wrong_type_returns_offset() {
+        assert_eq!(
+            Err(Error::UnexpectedToken { offset: 7 }),
+            from_slice::<User>(br#"{"id": "7", "name": "x"}"#)
+        );
+    }
+
+    #[test]
+    fn from_slice_numbers() {
+        assert_eq!(Ok(-12), from_slice::<i8>(b"-12"));
+        assert_eq!(Ok(2.5e3), from_slice::<f64>(b"2.5e3"));
+        assert_eq!(
+            Err(Error::NumberOutOfRange { offset: 1 }),
+            from_slice::<u8>(b" 256")
+        );
+        assert_eq!(
+            Err(Error::UnexpectedToken { offset: 0 }),
+            from_slice::<u32>(b"1.0")
+        );
+    }
+
+    #[test]
+    fn from_slice_borrowed_str_rejects_escapes() {
+        assert_eq!(Ok("plain"), from_slice::<&str>(br#""plain""#));
+        assert_eq!(
+            Err(Error::UnexpectedToken { offset: 0 }),
+            from_slice::<&str>(br#""a\nb""#)
+        );
+    }
+
+    #[test]
+    fn from_slice_trailing_data_returns_err() {
+        assert_eq!(
+            Err(Error::UnexpectedByte { offset: 5 }),
+            from_slice::<bool>(b"true false")
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use crate::parser::StreamError;
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Error {
+    /// Writing to the underlying writer failed.
+    Stream(StreamError),
+    /// Objects and arrays were nested deeper than `MAX_DEPTH`. `offset` is where the `[` or `{` that went too deep starts.
+    DepthExceeded {
+        max_depth: usize,
+        offset: usize,
+    },
+    /// A key was written outside an object, or twice in a row.
+    UnexpectedKey,
+    /// A value was written in an object without a key, or after the top level value.
+    UnexpectedValue,
+    /// An object or array was ended while it wasn't the innermost one open, or right after a key.
+    UnexpectedEnd,
+    /// The document was finished with no value, or with objects or arrays still open.
+    Incomplete,
+    /// JSON has no representation for NaN or infinity.
+    NonFiniteNumber,
+    /// A byte that can't appear at this point in the document.
+    UnexpectedByte {
+        offset: usize,
+    },
+    /// The document ended part way through a value.
+    UnexpectedEndOfInput {
+        offset: usize,
+    },
+    InvalidNumber {
+        offset: usize,
+    },
+    /// A string had an invalid escape, a raw control character or invalid UTF-8.
+    InvalidString {
+        offset: usize,
+    },
+    DecodeBufferTooSmall {
+        max_bytes: usize,
+        bytes: usize,
+    },
+    /// A valid token of the wrong kind for the type being decoded, such as a string where a number was expected.
+    UnexpectedToken {
+        offset: usize,
+    },
+    /// A number that doesn't fit in the type being decoded.
+    NumberOutOfRange {
+        offset: usize,
+    },
+    /// A required field was missing from an object.
+    MissingField {
+        name: &'static str,
+    },
+}
+
+impl From<StreamError> for Error {
+    fn from(error: StreamError) -> Self {
+        Error::Stream(error)
+    }
+}
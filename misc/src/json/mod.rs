@@ -0,0 +1,11 @@
+mod decode;
+mod error;
+mod tokenizer;
+mod writer;
+pub use decode::{from_slice, FromJson};
+pub use error::Error;
+pub use tokenizer::{JsonChars, JsonStr, Token, Tokenizer};
+pub use writer::{
+    json_size, resume_write_json_to, write_json_to, write_json_to_buffer, JsonWriter, ToJson,
+    MAX_DEPTH,
+};
@@ -0,0 +1,611 @@
+use super::error::Error;
+use super::writer::MAX_DEPTH;
+use crate::parser::RawIter;
+
+/// A single piece of a JSON document.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Token<'a> {
+    BeginObject,
+    EndObject,
+    BeginArray,
+    EndArray,
+    /// The key of an object member. The colon after it has been consumed.
+    Key(JsonStr<'a>),
+    String(JsonStr<'a>),
+    /// A number as written, such as `-1.5e3`. Already checked against the JSON grammar.
+    Number(&'a str),
+    Bool(bool),
+    Null,
+}
+
+/// A string as it appears in the document, without the quotes and with escapes still in place.
+/// Escapes are checked while tokenizing, so decoding can't fail.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct JsonStr<'a> {
+    raw: &'a str,
+}
+
+impl<'a> JsonStr<'a> {
+    /// The string with escapes still in place.
+    pub fn raw(&self) -> &'a str {
+        self.raw
+    }
+
+    /// Returns the string without decoding if it has no escapes.
+    pub fn as_str(&self) -> Option<&'a str> {
+        if self.raw.contains('\\') {
+            None
+        } else {
+            Some(self.raw)
+        }
+    }
+
+    /// Iterates over the decoded characters.
+    pub fn chars(&self) -> JsonChars<'a> {
+        JsonChars {
+            remaining: self.raw,
+        }
+    }
+
+    /// Whether the decoded string equals `value`, without decoding into a buffer.
+    pub fn matches(&self, value: &str) -> bool {
+        self.chars().eq(value.chars())
+    }
+
+    /// Decodes the string into the buffer.
+    pub fn decode<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, Error> {
+        let mut len = 0;
+        for c in self.chars() {
+            let end = len + c.len_utf8();
+            if end > buffer.len() {
+                return Err(Error::DecodeBufferTooSmall {
+                    max_bytes: buffer.len(),
+                    bytes: end,
+                });
+            }
+
+            c.encode_utf8(&mut buffer[len..end]);
+            len = end;
+        }
+
+        let buffer: &'b [u8] = buffer;
+        Ok(core::str::from_utf8(&buffer[..len]).unwrap())
+    }
+}
+
+/// The decoded characters of a `JsonStr`.
+#[derive(Clone, Debug)]
+pub struct JsonChars<'a> {
+    remaining: &'a str,
+}
+
+impl<'a> Iterator for JsonChars<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let rest = match self.remaining.strip_prefix('\\') {
+            Some(rest) => rest,
+            None => {
+                let c = self.remaining.chars().next()?;
+                self.remaining = &self.remaining[c.len_utf8()..];
+                return Some(c);
+            }
+        };
+
+        // Escapes were checked by the tokenizer.
+        let (c, len) = match rest.as_bytes()[0] {
+            b'b' => ('\u{8}', 1),
+            b'f' => ('\u{c}', 1),
+            b'n' => ('\n', 1),
+            b'r' => ('\r', 1),
+            b't' => ('\t', 1),
+            b'u' => {
+                let high = hex4(&rest.as_bytes()[1..]).unwrap();
+                if (0xd800..0xdc00).contains(&high) {
+                    let low = hex4(&rest.as_bytes()[7..]).unwrap();
+                    let code = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+                    (char::from_u32(code).unwrap(), 11)
+                } else {
+                    (char::from_u32(high).unwrap(), 5)
+                }
+            }
+            other => (other as char, 1),
+        };
+
+        self.remaining = &rest[len..];
+        Some(c)
+    }
+}
+
+/// What may come next in the document.
+#[derive(PartialEq, Copy, Clone, Debug)]
+enum Expect {
+    Value,
+    /// Right after `[`.
+    ValueOrEnd,
+    /// After a comma in an object.
+    Key,
+    /// Right after `{`.
+    KeyOrEnd,
+    CommaOrEnd,
+    /// The top level value is complete; only whitespace may follow.
+    Done,
+}
+
+/// Pulls tokens out of a JSON document one at a time, checking the structure as it goes. Nothing is allocated.
+/// Errors carry the byte offset where the problem was found. After an error the tokenizer yields nothing more.
+#[derive(Clone)]
+pub struct Tokenizer<'a> {
+    iter: RawIter<'a>,
+    /// Whether each open container is an object.
+    objects: [bool; MAX_DEPTH],
+    depth: usize,
+    expect: Expect,
+    /// Where the last token started.
+    token_start: usize,
+    failed: bool,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            iter: RawIter::new(data),
+            objects: [false; MAX_DEPTH],
+            depth: 0,
+            expect: Expect::Value,
+            token_start: 0,
+            failed: false,
+        }
+    }
+
+    /// The offset of the next byte to be read.
+    pub fn offset(&self) -> usize {
+        self.iter.index()
+    }
+
+    /// The offset where the last token returned started.
+    pub fn token_offset(&self) -> usize {
+        self.token_start
+    }
+
+    /// Returns the next token, or `None` once the document is complete and only whitespace is left.
+    pub fn next_token(&mut self) -> Result<Option<Token<'a>>, Error> {
+        if self.failed {
+            return Ok(None);
+        }
+
+        let token = self.read_token();
+        if token.is_err() {
+            self.failed = true;
+        }
+
+        token
+    }
+
+    /// Returns the next token without consuming it.
+    pub fn peek(&self) -> Result<Option<Token<'a>>, Error> {
+        self.clone().next_token()
+    }
+
+    fn read_token(&mut self) -> Result<Option<Token<'a>>, Error> {
+        loop {
+            self.skip_whitespace();
+            self.token_start = self.offset();
+
+            let byte = match self.iter.data().first() {
+                Some(byte) => *byte,
+                None if self.expect == Expect::Done => return Ok(None),
+                None => {
+                    return Err(Error::UnexpectedEndOfInput {
+                        offset: self.offset(),
+                    })
+                }
+            };
+
+            match (self.expect, byte) {
+                (Expect::Done, _) => return Err(self.unexpected_byte()),
+                (Expect::CommaOrEnd, b',') => {
+                    self.iter.advance(1);
+                    self.expect = if self.objects[self.depth - 1] {
+                        Expect::Key
+                    } else {
+                        Expect::Value
+                    };
+                }
+                (Expect::CommaOrEnd, b'}') | (Expect::KeyOrEnd, b'}') => {
+                    return self.close(true).map(Some)
+                }
+                (Expect::CommaOrEnd, b']') | (Expect::ValueOrEnd, b']') => {
+                    return self.close(false).map(Some)
+                }
+                (Expect::Key, b'"') | (Expect::KeyOrEnd, b'"') => return self.key().map(Some),
+                (Expect::Value, _) | (Expect::ValueOrEnd, _) => return self.value().map(Some),
+                _ => return Err(self.unexpected_byte()),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Token<'a>, Error> {
+        let token = match self.iter.data()[0] {
+            b'{' => return self.open(true),
+            b'[' => return self.open(false),
+            b'"' => Token::String(self.string()?),
+            b'-' | b'0'..=b'9' => Token::Number(self.number()?),
+            b't' => self.literal(b"true", Token::Bool(true))?,
+            b'f' => self.literal(b"false", Token::Bool(false))?,
+            b'n' => self.literal(b"null", Token::Null)?,
+            _ => return Err(self.unexpected_byte()),
+        };
+
+        self.after_value();
+        Ok(token)
+    }
+
+    fn key(&mut self) -> Result<Token<'a>, Error> {
+        let key = self.string()?;
+
+        self.skip_whitespace();
+        match self.iter.data().first() {
+            Some(b':') => self.iter.advance(1),
+            Some(_) => return Err(self.unexpected_byte()),
+            None => {
+                return Err(Error::UnexpectedEndOfInput {
+                    offset: self.offset(),
+                })
+            }
+        }
+
+        self.expect = Expect::Value;
+        Ok(Token::Key(key))
+    }
+
+    fn open(&mut self, is_object: bool) -> Result<Token<'a>, Error> {
+        if self.depth >= MAX_DEPTH {
+            return Err(Error::DepthExceeded {
+                max_depth: MAX_DEPTH,
+                offset: self.offset(),
+            });
+        }
+
+        self.objects[self.depth] = is_object;
+        self.depth += 1;
+        self.iter.advance(1);
+
+        if is_object {
+            self.expect = Expect::KeyOrEnd;
+            Ok(Token::BeginObject)
+        } else {
+            self.expect = Expect::ValueOrEnd;
+            Ok(Token::BeginArray)
+        }
+    }
+
+    fn close(&mut self, is_object: bool) -> Result<Token<'a>, Error> {
+        if self.objects[self.depth - 1] != is_object {
+            return Err(self.unexpected_byte());
+        }
+
+        self.depth -= 1;
+        self.iter.advance(1);
+        self.after_value();
+
+        Ok(if is_object {
+            Token::EndObject
+        } else {
+            Token::EndArray
+        })
+    }
+
+    fn after_value(&mut self) {
+        self.expect = if self.depth == 0 {
+            Expect::Done
+        } else {
+            Expect::CommaOrEnd
+        };
+    }
+
+    /// Reads a string starting at its opening quote, advancing past the closing one.
+    fn string(&mut self) -> Result<JsonStr<'a>, Error> {
+        let start = self.offset() + 1;
+        let data = &self.iter.data()[1..];
+        let mut i = 0;
+
+        loop {
+            let invalid = Error::InvalidString { offset: start + i };
+            match data.get(i) {
+                None => return Err(Error::UnexpectedEndOfInput { offset: start + i }),
+                Some(b'"') => break,
+                Some(b'\\') => i += escape_len(&data[i..]).ok_or(invalid)?,
+                Some(0x00..=0x1f) => return Err(invalid),
+                Some(_) => i += 1,
+            }
+        }
+
+        let raw = core::str::from_utf8(&data[..i]).map_err(|error| Error::InvalidString {
+            offset: start + error.valid_up_to(),
+        })?;
+
+        self.iter.advance(i + 2);
+        Ok(JsonStr { raw })
+    }
+
+    /// Reads `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`.
+    fn number(&mut self) -> Result<&'a str, Error> {
+        let start = self.offset();
+        let data = self.iter.data();
+        let invalid = |i: usize| Error::InvalidNumber { offset: start + i };
+        let digits = |from: usize| {
+            data[from..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit())
+                .count()
+        };
+
+        let mut i = if data[0] == b'-' { 1 } else { 0 };
+        match data.get(i) {
+            Some(b'0') => i += 1,
+            Some(b'1'..=b'9') => i += digits(i),
+            _ => return Err(invalid(i)),
+        }
+
+        if data.get(i) == Some(&b'.') {
+            i += 1;
+            match digits(i) {
+                0 => return Err(invalid(i)),
+                n => i += n,
+            }
+        }
+
+        if let Some(b'e' | b'E') = data.get(i) {
+            i += 1;
+            if let Some(b'+' | b'-') = data.get(i) {
+                i += 1;
+            }
+
+            match digits(i) {
+                0 => return Err(invalid(i)),
+                n => i += n,
+            }
+        }
+
+        // Leading zeros such as `01` aren't allowed, and nor is anything else run on.
+        if let Some(b) = data.get(i) {
+            if b.is_ascii_alphanumeric() || *b == b'.' {
+                return Err(invalid(i));
+            }
+        }
+
+        self.iter.advance(i);
+
+        // Only ASCII digits and signs were accepted.
+        Ok(core::str::from_utf8(&data[..i]).unwrap())
+    }
+
+    fn literal(&mut self, literal: &[u8], token: Token<'a>) -> Result<Token<'a>, Error> {
+        let data = self.iter.data();
+        if !data.starts_with(literal) {
+            let matched = data.iter().zip(literal).take_while(|(a, b)| a == b).count();
+            return Err(if matched == data.len() {
+                Error::UnexpectedEndOfInput {
+                    offset: self.offset() + matched,
+                }
+            } else {
+                Error::UnexpectedByte {
+                    offset: self.offset() + matched,
+                }
+            });
+        }
+
+        self.iter.advance(literal.len());
+        Ok(token)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let [b' ' | b'\t' | b'\n' | b'\r', ..] = self.iter.data() {
+            self.iter.advance(1);
+        }
+    }
+
+    fn unexpected_byte(&self) -> Error {
+        Error::UnexpectedByte {
+            offset: self.offset(),
+        }
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token().transpose()
+    }
+}
+
+/// The length of the escape at the start of `data`, or `None` if it is invalid. Surrogate pairs count as one escape.
+fn escape_len(data: &[u8]) -> Option<usize> {
+    match data.get(1)? {
+        b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => Some(2),
+        b'u' => {
+            let code = hex4(data.get(2..)?)?;
+            match code {
+                0xd800..=0xdbff => {
+                    // A high surrogate must be followed by a low one.
+                    if data.get(6..8)? != b"\\u" {
+                        return None;
+                    }
+
+                    match hex4(data.get(8..)?)? {
+                        0xdc00..=0xdfff => Some(12),
+                        _ => None,
+                    }
+                }
+                0xdc00..=0xdfff => None,
+                _ => Some(6),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Reads four hex digits.
+fn hex4(data: &[u8]) -> Option<u32> {
+    data.get(..4)?.iter().try_fold(0, |value, b| {
+        (*b as char).to_digit(16).map(|digit| value << 4 | digit)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens<'a>(data: &'a [u8], out: &mut [Token<'a>]) -> Result<usize, Error> {
+        let mut len = 0;
+        for token in Tokenizer::new(data) {
+            out[len] = token?;
+            len += 1;
+        }
+        Ok(len)
+    }
+
+    fn string(raw: &str) -> JsonStr<'_> {
+        JsonStr { raw }
+    }
+
+    #[test]
+    fn tokenize_document() {
+        let data = br#" {"a": [1, -2.5e3, true, false, null], "b": {}, "c": "x"} "#;
+        let mut out = [Token::Null; 16];
+        let len = tokens(data, &mut out).unwrap();
+
+        assert_eq!(
+            [
+                Token::BeginObject,
+                Token::Key(string("a")),
+                Token::BeginArray,
+                Token::Number("1"),
+                Token::Number("-2.5e3"),
+                Token::Bool(true),
+                Token::Bool(false),
+                Token::Null,
+                Token::EndArray,
+                Token::Key(string("b")),
+                Token::BeginObject,
+                Token::EndObject,
+                Token::Key(string("c")),
+                Token::String(string("x")),
+                Token::EndObject,
+            ],
+            out[..len]
+        );
+    }
+
+    #[test]
+    fn tokenize_scalar_document() {
+        let mut out = [Token::Null; 2];
+        assert_eq!(Ok(1), tokens(b"  42\n", &mut out));
+        assert_eq!(Token::Number("42"), out[0]);
+    }
+
+    #[test]
+    fn string_escapes_decode() {
+        let data = r#""a\"b\\c\/\né😀""#.as_bytes();
+        let mut tokenizer = Tokenizer::new(data);
+        let value = match tokenizer.next_token().unwrap() {
+            Some(Token::String(value)) => value,
+            other => panic!("expected a string, got {:?}", other),
+        };
+
+        assert_eq!(None, value.as_str());
+        assert!(value.matches("a\"b\\c/\né😀"));
+
+        let mut buffer = [0; 32];
+        assert_eq!(Ok("a\"b\\c/\né😀"), value.decode(&mut buffer));
+        assert!(matches!(
+            value.decode(&mut buffer[..4]),
+            Err(Error::DecodeBufferTooSmall { max_bytes: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn errors_report_offsets() {
+        let mut out = [Token::Null; 8];
+        assert_eq!(
+            Err(Error::UnexpectedByte { offset: 8 }),
+            tokens(br#"{"a": 1 "b": 2}"#, &mut out)
+        );
+        assert_eq!(
+            Err(Error::UnexpectedByte { offset: 5 }),
+            tokens(b"[1, 2}", &mut out)
+        );
+        assert_eq!(
+            Err(Error::InvalidNumber { offset: 2 }),
+            tokens(b"[01]", &mut out)
+        );
+        assert_eq!(
+            Err(Error::InvalidNumber { offset: 3 }),
+            tokens(b"[1.]", &mut out)
+        );
+        assert_eq!(
+            Err(Error::InvalidString { offset: 3 }),
+            tokens(br#"["a\x"]"#, &mut out)
+        );
+        assert_eq!(
+            Err(Error::InvalidString { offset: 2 }),
+            tokens(br#"["\udc00"]"#, &mut out)
+        );
+        assert_eq!(
+            Err(Error::UnexpectedByte { offset: 4 }),
+            tokens(b"[tru]", &mut out)
+        );
+        assert_eq!(
+            Err(Error::UnexpectedByte { offset: 3 }),
+            tokens(b"[1,]", &mut out)
+        );
+        assert_eq!(
+            Err(Error::UnexpectedByte { offset: 2 }),
+            tokens(b"1 2", &mut out)
+        );
+    }
+
+    #[test]
+    fn truncated_document_returns_err() {
+        let mut out = [Token::Null; 8];
+        assert_eq!(
+            Err(Error::UnexpectedEndOfInput { offset: 6 }),
+            tokens(br#"{"a": "#, &mut out)
+        );
+        assert_eq!(
+            Err(Error::UnexpectedEndOfInput { offset: 4 }),
+            tokens(br#"["ab"#, &mut out)
+        );
+        assert_eq!(
+            Err(Error::UnexpectedEndOfInput { offset: 0 }),
+            tokens(b"", &mut out)
+        );
+    }
+
+    #[test]
+    fn tokenizer_stops_after_error() {
+        let mut tokenizer = Tokenizer::new(b"[1 2]");
+        assert_eq!(Some(Ok(Token::BeginArray)), tokenizer.next());
+        assert_eq!(Some(Ok(Token::Number("1"))), tokenizer.next());
+        assert!(matches!(tokenizer.next(), Some(Err(_))));
+        assert_eq!(None, tokenizer.next());
+    }
+
+    #[test]
+    fn too_deep_returns_err() {
+        let mut data = [b' '; 2 * (MAX_DEPTH + 1)];
+        data.iter_mut().step_by(2).for_each(|b| *b = b'[');
+        let mut tokenizer = Tokenizer::new(&data);
+        let error = tokenizer.find_map(|token| token.err());
+
+        assert_eq!(
+            Some(Error::DepthExceeded {
+                max_depth: MAX_DEPTH,
+                offset: 2 * MAX_DEPTH
+            }),
+            error
+        );
+    }
+}
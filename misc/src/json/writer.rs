@@ -0,0 +1,563 @@
+use super::error::Error;
+use crate::http::response::{PassOutput, ResponseWriter, SizeCounter, SliceWriter, StreamError};
+
+/// How deeply objects and arrays may be nested.
+pub const MAX_DEPTH: usize = 32;
+
+/// A value that can write itself as JSON.
+///
+/// Writing may run more than once, to measure the output or to resume after backpressure, so a value must always write the same JSON.
+pub trait ToJson {
+    fn to_json<W: ResponseWriter>(&self, json: &mut JsonWriter<W>) -> Result<(), Error>;
+}
+
+/// Returns the size of the buffer that should be used for writing the value.
+pub fn json_size<T: ToJson + ?Sized>(value: &T) -> Result<usize, Error> {
+    write_json_to(value, SizeCounter::new())
+}
+
+/// Writes the value to the buffer, returning the number of written bytes.
+pub fn write_json_to_buffer<T: ToJson + ?Sized>(
+    value: &T,
+    buffer: &mut [u8],
+) -> Result<usize, Error> {
+    let size = json_size(value)?;
+    if buffer.len() < size {
+        return Err(Error::Stream(StreamError::BufferOverflow {
+            max_bytes: buffer.len(),
+            actual_size: size,
+        }));
+    }
+
+    write_json_to(value, SliceWriter::new(buffer))
+}
+
+/// Streams the value into the writer, returning the number of written bytes.
+/// Backpressure is reported as `StreamError::WouldBlock`, as in `Response::write_to`.
+pub fn write_json_to<T: ToJson + ?Sized, W: ResponseWriter>(
+    value: &T,
+    writer: W,
+) -> Result<usize, Error> {
+    resume_write_json_to(value, writer, 0)
+}
+
+/// Like `write_json_to`, but skips the first `offset` bytes, which were already written by an earlier call.
+pub fn resume_write_json_to<T: ToJson + ?Sized, W: ResponseWriter>(
+    value: &T,
+    writer: W,
+    offset: usize,
+) -> Result<usize, Error> {
+    let mut json = JsonWriter::new(PassOutput::new(writer, offset));
+    value.to_json(&mut json)?;
+    json.finish()
+}
+
+/// An object or array that is still open.
+#[derive(Copy, Clone)]
+struct Frame {
+    is_object: bool,
+    len: usize,
+    /// In an object, whether a key was written that still needs its value.
+    has_key: bool,
+}
+
+/// Writes a single JSON document piece by piece, adding the commas and checking that the structure is valid.
+pub struct JsonWriter<W: ResponseWriter> {
+    output: PassOutput<W>,
+    frames: [Frame; MAX_DEPTH],
+    depth: usize,
+    /// Whether the top level value has been started.
+    started: bool,
+}
+
+impl<W: ResponseWriter> JsonWriter<W> {
+    fn new(output: PassOutput<W>) -> Self {
+        Self {
+            output,
+            frames: [Frame {
+                is_object: false,
+                len: 0,
+                has_key: false,
+            }; MAX_DEPTH],
+            depth: 0,
+            started: false,
+        }
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), Error> {
+        self.open(true)
+    }
+
+    pub fn end_object(&mut self) -> Result<(), Error> {
+        self.close(true)
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), Error> {
+        self.open(false)
+    }
+
+    pub fn end_array(&mut self) -> Result<(), Error> {
+        self.close(false)
+    }
+
+    /// Writes the key of the next object member. Must be followed by its value.
+    pub fn key(&mut self, key: &str) -> Result<(), Error> {
+        let frame = match self.depth {
+            0 => return Err(Error::UnexpectedKey),
+            depth => &mut self.frames[depth - 1],
+        };
+
+        if !frame.is_object || frame.has_key {
+            return Err(Error::UnexpectedKey);
+        }
+
+        frame.has_key = true;
+        frame.len += 1;
+        if frame.len > 1 {
+            self.output.write(b",")?;
+        }
+
+        self.write_escaped(key)?;
+        self.output.write(b":").map_err(Error::from)
+    }
+
+    /// Writes an object member.
+    pub fn field<T: ToJson + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), Error> {
+        self.key(key)?;
+        value.to_json(self)
+    }
+
+    pub fn string(&mut self, value: &str) -> Result<(), Error> {
+        self.before_value()?;
+        self.write_escaped(value)
+    }
+
+    pub fn u64(&mut self, value: u64) -> Result<(), Error> {
+        use numtoa::NumToA;
+        self.before_value()?;
+        let mut buffer = [0; 20];
+        self.output
+            .write(value.numtoa(10, &mut buffer))
+            .map_err(Error::from)
+    }
+
+    pub fn i64(&mut self, value: i64) -> Result<(), Error> {
+        use numtoa::NumToA;
+        self.before_value()?;
+        let mut buffer = [0; 20];
+        self.output
+            .write(value.numtoa(10, &mut buffer))
+            .map_err(Error::from)
+    }
+
+    /// Writes the shortest decimal that reads back as the same value. NaN and infinities are rejected.
+    pub fn f64(&mut self, value: f64) -> Result<(), Error> {
+        self.float(value, value.is_finite())
+    }
+
+    /// Like `f64`, but shortest for an `f32`, so `0.1f32` is written as `0.1`.
+    pub fn f32(&mut self, value: f32) -> Result<(), Error> {
+        self.float(value, value.is_finite())
+    }
+
+    /// Writes a float with its own `Display`, which gives the shortest decimal for its type.
+    fn float<T: core::fmt::Display>(&mut self, value: T, is_finite: bool) -> Result<(), Error> {
+        if !is_finite {
+            return Err(Error::NonFiniteNumber);
+        }
+
+        self.before_value()?;
+        let mut output = FmtOutput {
+            output: &mut self.output,
+            error: None,
+        };
+
+        use core::fmt::Write;
+        if write!(output, "{}", value).is_err() {
+            // Formatting into the output can only fail with a stream error.
+            return Err(output.error.map_or(Error::Incomplete, Error::Stream));
+        }
+
+        Ok(())
+    }
+
+    pub fn bool(&mut self, value: bool) -> Result<(), Error> {
+        self.before_value()?;
+        let value: &[u8] = if value { b"true" } else { b"false" };
+        self.output.write(value).map_err(Error::from)
+    }
+
+    pub fn null(&mut self) -> Result<(), Error> {
+        self.before_value()?;
+        self.output.write(b"null").map_err(Error::from)
+    }
+
+    /// Checks the document is complete and flushes the writer, returning the size of the document.
+    fn finish(self) -> Result<usize, Error> {
+        if !self.started || self.depth > 0 {
+            return Err(Error::Incomplete);
+        }
+
+        self.output.finish().map_err(Error::from)
+    }
+
+    fn open(&mut self, is_object: bool) -> Result<(), Error> {
+        self.before_value()?;
+        if self.depth >= MAX_DEPTH {
+            return Err(Error::DepthExceeded {
+                max_depth: MAX_DEPTH,
+                offset: self.output.position(),
+            });
+        }
+
+        self.frames[self.depth] = Frame {
+            is_object,
+            len: 0,
+            has_key: false,
+        };
+        self.depth += 1;
+
+        let bracket: &[u8] = if is_object { b"{" } else { b"[" };
+        self.output.write(bracket).map_err(Error::from)
+    }
+
+    fn close(&mut self, is_object: bool) -> Result<(), Error> {
+        match self.depth {
+            0 => return Err(Error::UnexpectedEnd),
+            depth => {
+                let frame = self.frames[depth - 1];
+                if frame.is_object != is_object || frame.has_key {
+                    return Err(Error::UnexpectedEnd);
+                }
+            }
+        }
+
+        self.depth -= 1;
+        let bracket: &[u8] = if is_object { b"}" } else { b"]" };
+        self.output.write(bracket).map_err(Error::from)
+    }
+
+    /// Checks a value may be written here, writing the comma that separates it from the one before.
+    fn before_value(&mut self) -> Result<(), Error> {
+        let frame = match self.depth {
+            0 if self.started => return Err(Error::UnexpectedValue),
+            0 => {
+                self.started = true;
+                return Ok(());
+            }
+            depth => &mut self.frames[depth - 1],
+        };
+
+        if frame.is_object {
+            if !frame.has_key {
+                return Err(Error::UnexpectedValue);
+            }
+
+            frame.has_key = false;
+            return Ok(());
+        }
+
+        frame.len += 1;
+        if frame.len > 1 {
+            self.output.write(b",")?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the string in quotes, escaping quotes, backslashes and control characters.
+    fn write_escaped(&mut self, value: &str) -> Result<(), Error> {
+        self.output.write(b"\"")?;
+
+        let bytes = value.as_bytes();
+        let mut start = 0;
+        for (i, b) in bytes.iter().enumerate() {
+            let mut unicode = [b'\\', b'u', b'0', b'0', 0, 0];
+            let escape: &[u8] = match b {
+                b'"' => b"\\\"",
+                b'\\' => b"\\\\",
+                b'\n' => b"\\n",
+                b'\r' => b"\\r",
+                b'\t' => b"\\t",
+                0x08 => b"\\b",
+                0x0c => b"\\f",
+                0x00..=0x1f => {
+                    const HEX: &[u8; 16] = b"0123456789abcdef";
+                    unicode[4] = HEX[usize::from(b >> 4)];
+                    unicode[5] = HEX[usize::from(b & 0xf)];
+                    &unicode
+                }
+                _ => continue,
+            };
+
+            self.output.write(&bytes[start..i])?;
+            self.output.write(escape)?;
+            start = i + 1;
+        }
+
+        self.output.write(&bytes[start..])?;
+        self.output.write(b"\"").map_err(Error::from)
+    }
+}
+
+/// Lets `core::fmt` write numbers straight to the output, keeping the stream error.
+struct FmtOutput<'o, W: ResponseWriter> {
+    output: &'o mut PassOutput<W>,
+    error: Option<StreamError>,
+}
+
+impl<'o, W: ResponseWriter> core::fmt::Write for FmtOutput<'o, W> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.output.write(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            core::fmt::Error
+        })
+    }
+}
+
+impl ToJson for str {
+    fn to_json<W: ResponseWriter>(&self, json: &mut JsonWriter<W>) -> Result<(), Error> {
+        json.string(self)
+    }
+}
+
+impl ToJson for bool {
+    fn to_json<W: ResponseWriter>(&self, json: &mut JsonWriter<W>) -> Result<(), Error> {
+        json.bool(*self)
+    }
+}
+
+impl ToJson for f64 {
+    fn to_json<W: ResponseWriter>(&self, json: &mut JsonWriter<W>) -> Result<(), Error> {
+        json.f64(*self)
+    }
+}
+
+impl ToJson for f32 {
+    fn to_json<W: ResponseWriter>(&self, json: &mut JsonWriter<W>) -> Result<(), Error> {
+        json.f32(*self)
+    }
+}
+
+macro_rules! to_json_integers {
+    ($method:ident, $($integer:ty),*) => {
+        $(
+            impl ToJson for $integer {
+                fn to_json<W: ResponseWriter>(&self, json: &mut JsonWriter<W>) -> Result<(), Error> {
+                    json.$method(*self as _)
+                }
+            }
+        )*
+    };
+}
+
+to_json_integers!(u64, u8, u16, u32, u64, usize);
+to_json_integers!(i64, i8, i16, i32, i64, isize);
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json<W: ResponseWriter>(&self, json: &mut JsonWriter<W>) -> Result<(), Error> {
+        (**self).to_json(json)
+    }
+}
+
+/// `None` is written as `null`.
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json<W: ResponseWriter>(&self, json: &mut JsonWriter<W>) -> Result<(), Error> {
+        match self {
+            Some(value) => value.to_json(json),
+            None => json.null(),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json<W: ResponseWriter>(&self, json: &mut JsonWriter<W>) -> Result<(), Error> {
+        json.begin_array()?;
+        for value in self {
+            value.to_json(json)?;
+        }
+        json.end_array()
+    }
+}
+
+impl<T: ToJson, const N: usize> ToJson for [T; N] {
+    fn to_json<W: ResponseWriter>(&self, json: &mut JsonWriter<W>) -> Result<(), Error> {
+        self[..].to_json(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct User<'a> {
+        id: u32,
+        name: &'a str,
+        email: Option<&'a str>,
+        roles: [&'a str; 2],
+        score: f64,
+    }
+
+    impl<'a> ToJson for User<'a> {
+        fn to_json<W: ResponseWriter>(&self, json: &mut JsonWriter<W>) -> Result<(), Error> {
+            json.begin_object()?;
+            json.field("id", &self.id)?;
+            json.field("name", self.name)?;
+            json.field("email", &self.email)?;
+            json.field("roles", &self.roles)?;
+            json.field("score", &self.score)?;
+            json.end_object()
+        }
+    }
+
+    fn write<T: ToJson + ?Sized>(value: &T) -> ([u8; 256], usize) {
+        let mut buffer = [0; 256];
+        let len = write_json_to_buffer(value, &mut buffer).unwrap();
+        (buffer, len)
+    }
+
+    fn write_raw(
+        build: impl Fn(&mut JsonWriter<SliceWriter>) -> Result<(), Error>,
+    ) -> Result<([u8; 256], usize), Error> {
+        let mut buffer = [0; 256];
+        let mut json = JsonWriter::new(PassOutput::new(SliceWriter::new(&mut buffer), 0));
+        build(&mut json)?;
+        let len = json.finish()?;
+        Ok((buffer, len))
+    }
+
+    #[test]
+    fn write_struct() {
+        let user = User {
+            id: 7,
+            name: "Ada",
+            email: None,
+            roles: ["admin", "dev"],
+            score: -1.5,
+        };
+
+        let (buffer, len) = write(&user);
+        assert_eq!(
+            &br#"{"id":7,"name":"Ada","email":null,"roles":["admin","dev"],"score":-1.5}"#[..],
+            &buffer[..len]
+        );
+        assert_eq!(Ok(len), json_size(&user));
+    }
+
+    #[test]
+    fn write_escapes_strings() {
+        let (buffer, len) = write("say \"hi\"\\\n\t\u{1}é");
+        assert_eq!(r#""say \"hi\"\\\n\t\u0001é""#.as_bytes(), &buffer[..len]);
+    }
+
+    #[test]
+    fn write_numbers() {
+        let (buffer, len) = write(&[i64::MIN, 0, 42]);
+        assert_eq!(&b"[-9223372036854775808,0,42]"[..], &buffer[..len]);
+
+        let (buffer, len) = write(&[u64::MAX]);
+        assert_eq!(&b"[18446744073709551615]"[..], &buffer[..len]);
+
+        let (buffer, len) = write(&[0.1, 1e21]);
+        assert_eq!(&b"[0.1,1000000000000000000000]"[..], &buffer[..len]);
+
+        let (buffer, len) = write(&[0.1f32, 3.5]);
+        assert_eq!(&b"[0.1,3.5]"[..], &buffer[..len]);
+    }
+
+    #[test]
+    fn write_nested_empty_containers() {
+        let result = write_raw(|json| {
+            json.begin_array()?;
+            json.begin_object()?;
+            json.end_object()?;
+            json.begin_array()?;
+            json.end_array()?;
+            json.bool(true)?;
+            json.end_array()
+        });
+
+        let (buffer, len) = result.unwrap();
+        assert_eq!(&b"[{},[],true]"[..], &buffer[..len]);
+    }
+
+    #[test]
+    fn write_invalid_structure_returns_err() {
+        assert_eq!(
+            Err(Error::UnexpectedValue),
+            write_raw(|json| {
+                json.begin_object()?;
+                json.null()
+            })
+            .map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::UnexpectedKey),
+            write_raw(|json| {
+                json.begin_array()?;
+                json.key("a")
+            })
+            .map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::UnexpectedEnd),
+            write_raw(|json| {
+                json.begin_object()?;
+                json.key("a")?;
+                json.end_object()
+            })
+            .map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::UnexpectedValue),
+            write_raw(|json| {
+                json.null()?;
+                json.null()
+            })
+            .map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::Incomplete),
+            write_raw(|json| json.begin_array()).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn write_non_finite_returns_err() {
+        let mut buffer = [0; 16];
+        assert_eq!(
+            Err(Error::NonFiniteNumber),
+            write_json_to_buffer(&f64::NAN, &mut buffer)
+        );
+    }
+
+    #[test]
+    fn write_too_deep_returns_err() {
+        let result = write_raw(|json| {
+            for _ in 0..=MAX_DEPTH {
+                json.begin_array()?;
+            }
+            Ok(())
+        });
+
+        assert_eq!(
+            Err(Error::DepthExceeded {
+                max_depth: MAX_DEPTH,
+                offset: MAX_DEPTH
+            }),
+            result.map(|_| ())
+        );
+    }
+
+    #[test]
+    fn write_to_small_buffer_returns_err() {
+        let mut buffer = [0; 4];
+        assert_eq!(
+            Err(Error::Stream(StreamError::BufferOverflow {
+                max_bytes: 4,
+                actual_size: 7
+            })),
+            write_json_to_buffer("hello", &mut buffer)
+        );
+    }
+}
@@ -1,6 +1,10 @@
 #![no_std]
 
+#[cfg(feature = "std")]
+extern crate std;
+
 pub mod http;
+pub mod json;
 
 mod parser {
     #[derive(PartialEq, Copy, Clone, Debug)]
@@ -9,9 +13,14 @@ mod parser {
             max_bytes: usize,
             actual_size: usize,
         },
+        /// The sink can't take any more bytes right now. `bytes_written` is how much of the message was accepted, and so where writing should resume.
+        WouldBlock { bytes_written: usize },
+        /// The sink failed and can't be written to any more, such as a socket that was closed.
+        WriteFailed,
     }
 
     /// Struct for iterating over a raw request
+    #[derive(Clone)]
     pub struct RawIter<'a> {
         data: &'a [u8],
         index: usize,
@@ -23,7 +32,7 @@ mod parser {
         }
 
         /// Immutable handle to the data
-        pub fn data(&self) -> &[u8] {
+        pub fn data(&self) -> &'a [u8] {
             &self.data[self.index..]
         }
 
@@ -37,4 +46,11 @@ mod parser {
             self.index
         }
     }
+
+    /// Returns the index of the first occurrence of `needle` in `haystack`
+    pub fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        haystack
+            .windows(needle.len())
+            .position(|window| window == needle)
+    }
 }